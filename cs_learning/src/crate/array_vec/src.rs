@@ -1,6 +1,996 @@
+// 固定長配列をバッファに使う、ヒープを使わない（または使うのを遅らせる）可変長ベクター
+
+// ---------------------------------------------------------
+// ArrayVec: 容量固定のスタック上ベクター
+// ---------------------------------------------------------
+
+// ファイル側のモジュール名（main.rs の `mod array_vec`）と同じ名前だが、
+// 他の週と同じく「ファイル = 単元、内側の mod = 課題」という構成に揃えている
+#[allow(clippy::module_inception)]
 pub mod array_vec {
-  fn array_example() {
-    // 固定長配列
-    let arr: [i32; 5] = [1,2,3,4,5];
-  }
-}
\ No newline at end of file
+    use std::fmt;
+    use std::mem::{ManuallyDrop, MaybeUninit};
+    use std::ops::{Deref, DerefMut};
+    use std::ptr;
+    use std::slice;
+
+    /// 容量を超えて要素を追加しようとしたことを表すエラー
+    ///
+    /// 追加できなかった値を保持しているので、`element()` で取り戻せる。
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CapacityError<T> {
+        element: T,
+    }
+
+    impl<T> CapacityError<T> {
+        /// 追加できなかった値を取り出す
+        pub fn element(self) -> T {
+            self.element
+        }
+    }
+
+    impl<T> fmt::Display for CapacityError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "insufficient capacity")
+        }
+    }
+
+    impl<T: fmt::Debug> std::error::Error for CapacityError<T> {}
+
+    /// 容量 N の固定長配列を使うベクター
+    ///
+    /// 要素は `[MaybeUninit<T>; N]` に直接格納されるため、ヒープ確保は一切行わない。
+    /// 先頭 len 個だけが初期化済みで、残りは未初期化のまま。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut vec: ArrayVec<i32, 4> = ArrayVec::new();
+    /// vec.push(1);
+    /// vec.push(2);
+    /// assert_eq!(vec.pop(), Some(2));
+    /// ```
+    pub struct ArrayVec<T, const N: usize> {
+        data: [MaybeUninit<T>; N], // 要素を格納する配列（先頭 len 個のみ初期化済み）
+        len: usize,                // 現在の要素数
+    }
+
+    impl<T, const N: usize> ArrayVec<T, N> {
+        /// 新しい空のベクターを作成
+        pub const fn new() -> Self {
+            ArrayVec {
+                // 未初期化のまま確保するだけなので、T の初期化は不要
+                data: [const { MaybeUninit::uninit() }; N],
+                len: 0,
+            }
+        }
+
+        /// 要素を末尾に追加
+        ///
+        /// # Panics
+        /// 容量がいっぱいの場合
+        pub fn push(&mut self, value: T) {
+            if self.try_push(value).is_err() {
+                panic!("ArrayVec::push: capacity {} exceeded", N);
+            }
+        }
+
+        /// 要素を末尾に追加（容量がいっぱいなら値をエラーに包んで返す）
+        pub fn try_push(&mut self, value: T) -> Result<(), CapacityError<T>> {
+            if self.len == N {
+                return Err(CapacityError { element: value });
+            }
+            unsafe {
+                ptr::write(self.as_mut_ptr().add(self.len), value);
+            }
+            self.len += 1;
+            Ok(())
+        }
+
+        /// 末尾の要素を削除して返す
+        pub fn pop(&mut self) -> Option<T> {
+            if self.len == 0 {
+                None
+            } else {
+                self.len -= 1;
+                unsafe { Some(ptr::read(self.as_ptr().add(self.len))) }
+            }
+        }
+
+        /// index の位置に要素を挿入し、それ以降の要素を 1 つ後ろにずらす
+        ///
+        /// # Panics
+        /// index > len の場合、または容量がいっぱいの場合
+        pub fn insert(&mut self, index: usize, value: T) {
+            if self.try_insert(index, value).is_err() {
+                panic!("ArrayVec::insert: capacity {} exceeded", N);
+            }
+        }
+
+        /// index の位置に要素を挿入（容量がいっぱいなら値をエラーに包んで返す）
+        ///
+        /// # Panics
+        /// index > len の場合
+        pub fn try_insert(&mut self, index: usize, value: T) -> Result<(), CapacityError<T>> {
+            assert!(
+                index <= self.len,
+                "insertion index (is {}) should be <= len (is {})",
+                index,
+                self.len
+            );
+            if self.len == N {
+                return Err(CapacityError { element: value });
+            }
+
+            unsafe {
+                let p = self.as_mut_ptr().add(index);
+                ptr::copy(p, p.add(1), self.len - index);
+                ptr::write(p, value);
+            }
+            self.len += 1;
+            Ok(())
+        }
+
+        /// index の要素を取り除いて返し、それ以降の要素を 1 つ前に詰める
+        ///
+        /// # Panics
+        /// index >= len の場合
+        pub fn remove(&mut self, index: usize) -> T {
+            assert!(
+                index < self.len,
+                "removal index (is {}) should be < len (is {})",
+                index,
+                self.len
+            );
+
+            unsafe {
+                let p = self.as_mut_ptr().add(index);
+                let value = ptr::read(p);
+                ptr::copy(p.add(1), p, self.len - index - 1);
+                self.len -= 1;
+                value
+            }
+        }
+
+        /// index の要素を取り除いて返し、空いた位置に末尾の要素を移す（O(1)）
+        ///
+        /// # Panics
+        /// index >= len の場合
+        pub fn swap_remove(&mut self, index: usize) -> T {
+            assert!(
+                index < self.len,
+                "swap_remove index (is {}) should be < len (is {})",
+                index,
+                self.len
+            );
+
+            unsafe {
+                let base = self.as_mut_ptr();
+                let value = ptr::read(base.add(index));
+                ptr::copy(base.add(self.len - 1), base.add(index), 1);
+                self.len -= 1;
+                value
+            }
+        }
+
+        /// 先頭 len 個だけを残し、残りを drop する
+        pub fn truncate(&mut self, len: usize) {
+            if len >= self.len {
+                return;
+            }
+            let remaining = self.len - len;
+            unsafe {
+                let tail = ptr::slice_from_raw_parts_mut(self.as_mut_ptr().add(len), remaining);
+                // drop 中にパニックしても二重 drop しないよう、先に len を更新する
+                self.len = len;
+                ptr::drop_in_place(tail);
+            }
+        }
+
+        /// 全要素を drop する
+        pub fn clear(&mut self) {
+            self.truncate(0);
+        }
+
+        /// インデックスで要素を取得
+        pub fn get(&self, index: usize) -> Option<&T> {
+            self.as_slice().get(index)
+        }
+
+        /// 現在の要素数
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        /// 容量（常に N）
+        pub const fn capacity(&self) -> usize {
+            N
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        /// 容量いっぱいかどうか
+        pub fn is_full(&self) -> bool {
+            self.len == N
+        }
+
+        /// あと何個追加できるか
+        pub fn remaining_capacity(&self) -> usize {
+            N - self.len
+        }
+
+        /// 全要素をスライスとして取得
+        pub fn as_slice(&self) -> &[T] {
+            // 先頭 len 個は初期化済み
+            unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+        }
+
+        /// 全要素を可変スライスとして取得
+        pub fn as_mut_slice(&mut self) -> &mut [T] {
+            unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+        }
+
+        /// バッファ先頭への生ポインタ
+        pub fn as_ptr(&self) -> *const T {
+            // MaybeUninit<T> は T と同じレイアウトなので、そのままキャストできる
+            self.data.as_ptr() as *const T
+        }
+
+        /// バッファ先頭への可変な生ポインタ
+        pub fn as_mut_ptr(&mut self) -> *mut T {
+            self.data.as_mut_ptr() as *mut T
+        }
+
+        /// 先頭から順に要素への参照を返すイテレータ
+        pub fn iter(&self) -> slice::Iter<'_, T> {
+            self.as_slice().iter()
+        }
+
+        /// 先頭から順に要素への可変参照を返すイテレータ
+        pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+            self.as_mut_slice().iter_mut()
+        }
+    }
+
+    impl<T, const N: usize> Drop for ArrayVec<T, N> {
+        fn drop(&mut self) {
+            // 初期化済みの要素だけを drop する（配列自体はスタック上なので解放は不要）
+            self.clear();
+        }
+    }
+
+    impl<T, const N: usize> Default for ArrayVec<T, N> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T, const N: usize> Deref for ArrayVec<T, N> {
+        type Target = [T];
+
+        fn deref(&self) -> &[T] {
+            self.as_slice()
+        }
+    }
+
+    impl<T, const N: usize> DerefMut for ArrayVec<T, N> {
+        fn deref_mut(&mut self) -> &mut [T] {
+            self.as_mut_slice()
+        }
+    }
+
+    impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+        fn clone(&self) -> Self {
+            let mut vec = ArrayVec::new();
+            for item in self.iter() {
+                vec.push(item.clone());
+            }
+            vec
+        }
+    }
+
+    impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    impl<T: PartialEq, const N: usize> PartialEq for ArrayVec<T, N> {
+        fn eq(&self, other: &Self) -> bool {
+            self.as_slice() == other.as_slice()
+        }
+    }
+
+    impl<T: Eq, const N: usize> Eq for ArrayVec<T, N> {}
+
+    /// ArrayVec を消費して要素を所有権ごと返すイテレータ
+    pub struct IntoIter<T, const N: usize> {
+        data: [MaybeUninit<T>; N],
+        start: usize, // 次に front から返す位置
+        end: usize,   // back から返す位置の 1 つ後ろ
+    }
+
+    impl<T, const N: usize> Iterator for IntoIter<T, N> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            if self.start == self.end {
+                None
+            } else {
+                let item = unsafe { self.data[self.start].assume_init_read() };
+                self.start += 1;
+                Some(item)
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = self.end - self.start;
+            (len, Some(len))
+        }
+    }
+
+    impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+        fn next_back(&mut self) -> Option<T> {
+            if self.start == self.end {
+                None
+            } else {
+                self.end -= 1;
+                Some(unsafe { self.data[self.end].assume_init_read() })
+            }
+        }
+    }
+
+    impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+    impl<T, const N: usize> Drop for IntoIter<T, N> {
+        fn drop(&mut self) {
+            // まだ返していない要素を drop
+            for _ in &mut *self {}
+        }
+    }
+
+    impl<T, const N: usize> IntoIterator for ArrayVec<T, N> {
+        type Item = T;
+        type IntoIter = IntoIter<T, N>;
+
+        fn into_iter(self) -> IntoIter<T, N> {
+            // ArrayVec の Drop が走らないようにして、配列の中身を IntoIter に移す
+            let vec = ManuallyDrop::new(self);
+            IntoIter {
+                data: unsafe { ptr::read(&vec.data) },
+                start: 0,
+                end: vec.len,
+            }
+        }
+    }
+
+    impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+        type Item = &'a T;
+        type IntoIter = slice::Iter<'a, T>;
+
+        fn into_iter(self) -> slice::Iter<'a, T> {
+            self.iter()
+        }
+    }
+
+    impl<'a, T, const N: usize> IntoIterator for &'a mut ArrayVec<T, N> {
+        type Item = &'a mut T;
+        type IntoIter = slice::IterMut<'a, T>;
+
+        fn into_iter(self) -> slice::IterMut<'a, T> {
+            self.iter_mut()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        /// drop 回数を数えるテスト用の型
+        struct DropCounter {
+            count: Arc<AtomicUsize>,
+        }
+
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.count.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        /// DropCounter を n 個持つベクターを作る
+        fn counters<const N: usize>(n: usize) -> (ArrayVec<DropCounter, N>, Arc<AtomicUsize>) {
+            let count = Arc::new(AtomicUsize::new(0));
+            let mut vec = ArrayVec::new();
+            for _ in 0..n {
+                vec.push(DropCounter {
+                    count: count.clone(),
+                });
+            }
+            (vec, count)
+        }
+
+        #[test]
+        fn test_new() {
+            let vec: ArrayVec<i32, 4> = ArrayVec::new();
+            assert_eq!(vec.len(), 0);
+            assert_eq!(vec.capacity(), 4);
+            assert!(vec.is_empty());
+            assert!(!vec.is_full());
+        }
+
+        #[test]
+        fn test_no_heap_storage() {
+            // 要素の配列と len だけを持ち、ヒープへのポインタを持たない
+            use std::mem::size_of;
+            assert_eq!(
+                size_of::<ArrayVec<u64, 4>>(),
+                4 * size_of::<u64>() + size_of::<usize>()
+            );
+
+            let vec: ArrayVec<u64, 4> = ArrayVec::new();
+            let start = &vec as *const _ as *const u8;
+            let end = start.wrapping_add(size_of::<ArrayVec<u64, 4>>());
+            let data = vec.as_ptr() as *const u8;
+            assert!(start <= data && data < end);
+        }
+
+        #[test]
+        fn test_push_and_pop() {
+            let mut vec: ArrayVec<i32, 3> = ArrayVec::new();
+            vec.push(1);
+            vec.push(2);
+            vec.push(3);
+
+            assert!(vec.is_full());
+            assert_eq!(vec.remaining_capacity(), 0);
+            assert_eq!(vec.pop(), Some(3));
+            assert_eq!(vec.pop(), Some(2));
+            assert_eq!(vec.len(), 1);
+            assert_eq!(vec.get(0), Some(&1));
+            assert_eq!(vec.get(1), None);
+        }
+
+        #[test]
+        fn test_try_push_full() {
+            let mut vec: ArrayVec<String, 1> = ArrayVec::new();
+            assert!(vec.try_push("a".to_string()).is_ok());
+
+            let err = vec.try_push("b".to_string()).unwrap_err();
+            assert_eq!(err.to_string(), "insufficient capacity");
+            assert_eq!(err.element(), "b");
+            assert_eq!(vec.len(), 1);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_push_full_panics() {
+            let mut vec: ArrayVec<i32, 2> = ArrayVec::new();
+            vec.push(1);
+            vec.push(2);
+            vec.push(3);
+        }
+
+        #[test]
+        fn test_zero_capacity() {
+            let mut vec: ArrayVec<i32, 0> = ArrayVec::new();
+            assert!(vec.is_full());
+            assert_eq!(vec.try_push(1).unwrap_err().element(), 1);
+            assert_eq!(vec.pop(), None);
+        }
+
+        #[test]
+        fn test_insert_and_remove() {
+            let mut vec: ArrayVec<i32, 5> = ArrayVec::new();
+            vec.insert(0, 2);
+            vec.insert(0, 0);
+            vec.insert(1, 1);
+            vec.insert(3, 3);
+            assert_eq!(vec.as_slice(), &[0, 1, 2, 3]);
+
+            assert_eq!(vec.remove(1), 1);
+            assert_eq!(vec.swap_remove(0), 0);
+            assert_eq!(vec.as_slice(), &[3, 2]);
+
+            vec.insert(2, 4);
+            vec.insert(0, 5);
+            vec.insert(0, 6);
+            assert!(vec.is_full());
+            assert_eq!(vec.try_insert(0, 7).unwrap_err().element(), 7);
+            assert_eq!(vec.as_slice(), &[6, 5, 3, 2, 4]);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_insert_out_of_bounds() {
+            let mut vec: ArrayVec<i32, 4> = ArrayVec::new();
+            vec.insert(1, 1);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_remove_out_of_bounds() {
+            let mut vec: ArrayVec<i32, 4> = ArrayVec::new();
+            vec.push(1);
+            vec.remove(1);
+        }
+
+        #[test]
+        fn test_deref_slice() {
+            let mut vec: ArrayVec<i32, 8> = ArrayVec::new();
+            for x in [5, 2, 8, 1, 9] {
+                vec.push(x);
+            }
+
+            vec.sort();
+            assert_eq!(&vec[..], &[1, 2, 5, 8, 9]);
+            assert_eq!(vec[2], 5);
+            assert_eq!(vec.binary_search(&8), Ok(3));
+            vec[0] = 10;
+            assert_eq!(vec.first(), Some(&10));
+        }
+
+        #[test]
+        fn test_iterators() {
+            let mut vec: ArrayVec<i32, 4> = ArrayVec::new();
+            for i in 0..4 {
+                vec.push(i);
+            }
+
+            for x in &mut vec {
+                *x *= 10;
+            }
+            let collected: Vec<i32> = vec.iter().copied().collect();
+            assert_eq!(collected, vec![0, 10, 20, 30]);
+
+            let mut iter = vec.clone().into_iter();
+            assert_eq!(iter.next(), Some(0));
+            assert_eq!(iter.next_back(), Some(30));
+            assert_eq!(iter.len(), 2);
+
+            let owned: Vec<i32> = vec.into_iter().rev().collect();
+            assert_eq!(owned, vec![30, 20, 10, 0]);
+        }
+
+        #[test]
+        fn test_drop_count() {
+            let (vec, count) = counters::<8>(5);
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn test_drop_after_pop_and_truncate() {
+            let (mut vec, count) = counters::<8>(6);
+
+            drop(vec.pop());
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+            drop(vec.remove(0));
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+            vec.truncate(2);
+            assert_eq!(count.load(Ordering::SeqCst), 4);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 6);
+        }
+
+        #[test]
+        fn test_into_iter_drops_remaining() {
+            let (vec, count) = counters::<4>(4);
+
+            let mut iter = vec.into_iter();
+            drop(iter.next());
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+
+            drop(iter);
+            assert_eq!(count.load(Ordering::SeqCst), 4);
+        }
+
+        #[test]
+        fn test_clone_and_eq() {
+            let mut vec: ArrayVec<String, 4> = ArrayVec::new();
+            vec.push("a".to_string());
+            vec.push("b".to_string());
+
+            let cloned = vec.clone();
+            assert_eq!(vec, cloned);
+            assert_eq!(format!("{:?}", cloned), r#"["a", "b"]"#);
+        }
+    }
+}
+
+// ---------------------------------------------------------
+// SmallVec: 少数の要素はインラインに、溢れたらヒープへ
+// ---------------------------------------------------------
+
+pub mod small_vec {
+    use super::array_vec::{self, ArrayVec};
+    use crate::week1_basic_structures::my_vec::{self, MyVec};
+    use std::fmt;
+    use std::mem;
+    use std::ops::{Deref, DerefMut};
+    use std::slice;
+
+    /// 要素の格納先
+    enum Storage<T, const N: usize> {
+        /// N 個以下の間は ArrayVec に直接格納する（ヒープ確保なし）
+        Inline(ArrayVec<T, N>),
+        /// N 個を超えたら MyVec のヒープバッファに移す
+        Heap(MyVec<T>),
+    }
+
+    /// N 個まではインラインに格納し、溢れたらヒープに移るベクター
+    ///
+    /// 小さいベクターの大半は数個の要素しか持たないので、その間はヒープ確保を避けられる。
+    /// 一度ヒープに移ったら、要素が減ってもインラインには戻らない。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut vec: SmallVec<i32, 2> = SmallVec::new();
+    /// vec.push(1);
+    /// vec.push(2);
+    /// assert!(!vec.spilled());
+    /// vec.push(3);
+    /// assert!(vec.spilled());
+    /// ```
+    pub struct SmallVec<T, const N: usize> {
+        storage: Storage<T, N>,
+    }
+
+    impl<T, const N: usize> SmallVec<T, N> {
+        /// 新しい空のベクターを作成（ヒープ確保はしない）
+        pub fn new() -> Self {
+            SmallVec {
+                storage: Storage::Inline(ArrayVec::new()),
+            }
+        }
+
+        /// 要素を末尾に追加
+        ///
+        /// インラインの容量がいっぱいのときは、全要素をヒープへ移してから追加する。
+        pub fn push(&mut self, value: T) {
+            match &mut self.storage {
+                Storage::Inline(inline) => {
+                    if let Err(e) = inline.try_push(value) {
+                        self.spill().push(e.element());
+                    }
+                }
+                Storage::Heap(heap) => heap.push(value),
+            }
+        }
+
+        /// 末尾の要素を削除して返す
+        pub fn pop(&mut self) -> Option<T> {
+            match &mut self.storage {
+                Storage::Inline(inline) => inline.pop(),
+                Storage::Heap(heap) => heap.pop(),
+            }
+        }
+
+        /// index の位置に要素を挿入し、それ以降の要素を 1 つ後ろにずらす
+        ///
+        /// # Panics
+        /// index > len の場合
+        pub fn insert(&mut self, index: usize, value: T) {
+            match &mut self.storage {
+                Storage::Inline(inline) => {
+                    if let Err(e) = inline.try_insert(index, value) {
+                        self.spill().insert(index, e.element());
+                    }
+                }
+                Storage::Heap(heap) => heap.insert(index, value),
+            }
+        }
+
+        /// index の要素を取り除いて返し、それ以降の要素を 1 つ前に詰める
+        ///
+        /// # Panics
+        /// index >= len の場合
+        pub fn remove(&mut self, index: usize) -> T {
+            match &mut self.storage {
+                Storage::Inline(inline) => inline.remove(index),
+                Storage::Heap(heap) => heap.remove(index),
+            }
+        }
+
+        /// インデックスで要素を取得
+        pub fn get(&self, index: usize) -> Option<&T> {
+            self.as_slice().get(index)
+        }
+
+        /// 現在の要素数
+        pub fn len(&self) -> usize {
+            match &self.storage {
+                Storage::Inline(inline) => inline.len(),
+                Storage::Heap(heap) => heap.len(),
+            }
+        }
+
+        /// 現在の容量（インラインの間は N）
+        pub fn capacity(&self) -> usize {
+            match &self.storage {
+                Storage::Inline(_) => N,
+                Storage::Heap(heap) => heap.capacity(),
+            }
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// ヒープに移っているかどうか
+        pub fn spilled(&self) -> bool {
+            matches!(self.storage, Storage::Heap(_))
+        }
+
+        /// 全要素をスライスとして取得
+        pub fn as_slice(&self) -> &[T] {
+            match &self.storage {
+                Storage::Inline(inline) => inline.as_slice(),
+                Storage::Heap(heap) => heap.as_slice(),
+            }
+        }
+
+        /// 全要素を可変スライスとして取得
+        pub fn as_mut_slice(&mut self) -> &mut [T] {
+            match &mut self.storage {
+                Storage::Inline(inline) => inline.as_mut_slice(),
+                Storage::Heap(heap) => heap.as_mut_slice(),
+            }
+        }
+
+        /// 先頭から順に要素への参照を返すイテレータ
+        pub fn iter(&self) -> slice::Iter<'_, T> {
+            self.as_slice().iter()
+        }
+
+        /// 先頭から順に要素への可変参照を返すイテレータ
+        pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+            self.as_mut_slice().iter_mut()
+        }
+
+        /// インラインの要素をすべてヒープへ移す（内部関数）
+        ///
+        /// 移した先の MyVec を返す。既にヒープにある場合はそのまま返す。
+        fn spill(&mut self) -> &mut MyVec<T> {
+            if let Storage::Inline(inline) = &mut self.storage {
+                // 溢れた直後にもう 1 つ追加されるので、倍の容量を確保しておく
+                let mut heap = MyVec::with_capacity((N * 2).max(1));
+                heap.extend(mem::take(inline));
+                self.storage = Storage::Heap(heap);
+            }
+            match &mut self.storage {
+                Storage::Heap(heap) => heap,
+                Storage::Inline(_) => unreachable!(),
+            }
+        }
+    }
+
+    impl<T, const N: usize> Default for SmallVec<T, N> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T, const N: usize> Deref for SmallVec<T, N> {
+        type Target = [T];
+
+        fn deref(&self) -> &[T] {
+            self.as_slice()
+        }
+    }
+
+    impl<T, const N: usize> DerefMut for SmallVec<T, N> {
+        fn deref_mut(&mut self) -> &mut [T] {
+            self.as_mut_slice()
+        }
+    }
+
+    impl<T: fmt::Debug, const N: usize> fmt::Debug for SmallVec<T, N> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    impl<T, const N: usize> Extend<T> for SmallVec<T, N> {
+        fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+            for item in iter {
+                self.push(item);
+            }
+        }
+    }
+
+    impl<T, const N: usize> FromIterator<T> for SmallVec<T, N> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            let mut vec = SmallVec::new();
+            vec.extend(iter);
+            vec
+        }
+    }
+
+    /// SmallVec を消費して要素を所有権ごと返すイテレータ
+    pub enum IntoIter<T, const N: usize> {
+        Inline(array_vec::IntoIter<T, N>),
+        Heap(my_vec::IntoIter<T>),
+    }
+
+    impl<T, const N: usize> Iterator for IntoIter<T, N> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            match self {
+                IntoIter::Inline(iter) => iter.next(),
+                IntoIter::Heap(iter) => iter.next(),
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            match self {
+                IntoIter::Inline(iter) => iter.size_hint(),
+                IntoIter::Heap(iter) => iter.size_hint(),
+            }
+        }
+    }
+
+    impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+        fn next_back(&mut self) -> Option<T> {
+            match self {
+                IntoIter::Inline(iter) => iter.next_back(),
+                IntoIter::Heap(iter) => iter.next_back(),
+            }
+        }
+    }
+
+    impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+    impl<T, const N: usize> IntoIterator for SmallVec<T, N> {
+        type Item = T;
+        type IntoIter = IntoIter<T, N>;
+
+        fn into_iter(self) -> IntoIter<T, N> {
+            match self.storage {
+                Storage::Inline(inline) => IntoIter::Inline(inline.into_iter()),
+                Storage::Heap(heap) => IntoIter::Heap(heap.into_iter()),
+            }
+        }
+    }
+
+    impl<'a, T, const N: usize> IntoIterator for &'a SmallVec<T, N> {
+        type Item = &'a T;
+        type IntoIter = slice::Iter<'a, T>;
+
+        fn into_iter(self) -> slice::Iter<'a, T> {
+            self.iter()
+        }
+    }
+
+    impl<'a, T, const N: usize> IntoIterator for &'a mut SmallVec<T, N> {
+        type Item = &'a mut T;
+        type IntoIter = slice::IterMut<'a, T>;
+
+        fn into_iter(self) -> slice::IterMut<'a, T> {
+            self.iter_mut()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        struct DropCounter {
+            count: Arc<AtomicUsize>,
+        }
+
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.count.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        #[test]
+        fn test_stays_inline() {
+            let mut vec: SmallVec<i32, 4> = SmallVec::new();
+            for i in 0..4 {
+                vec.push(i);
+            }
+
+            assert!(!vec.spilled());
+            assert_eq!(vec.capacity(), 4);
+            assert_eq!(vec.as_slice(), &[0, 1, 2, 3]);
+            assert_eq!(vec.pop(), Some(3));
+            assert_eq!(vec.get(0), Some(&0));
+        }
+
+        #[test]
+        fn test_spill_to_heap() {
+            let mut vec: SmallVec<String, 2> = SmallVec::new();
+            vec.push("a".to_string());
+            vec.push("b".to_string());
+            vec.push("c".to_string());
+
+            assert!(vec.spilled());
+            assert!(vec.capacity() >= 3);
+            assert_eq!(vec.as_slice(), &["a", "b", "c"]);
+
+            // 要素が減ってもヒープのまま
+            vec.pop();
+            vec.pop();
+            assert!(vec.spilled());
+            assert_eq!(vec.len(), 1);
+        }
+
+        #[test]
+        fn test_insert_spills() {
+            let mut vec: SmallVec<i32, 3> = SmallVec::new();
+            vec.insert(0, 3);
+            vec.insert(0, 1);
+            vec.insert(1, 2);
+            assert!(!vec.spilled());
+
+            vec.insert(0, 0);
+            assert!(vec.spilled());
+            assert_eq!(vec.as_slice(), &[0, 1, 2, 3]);
+            assert_eq!(vec.remove(1), 1);
+            assert_eq!(vec.as_slice(), &[0, 2, 3]);
+        }
+
+        #[test]
+        fn test_zero_inline_capacity() {
+            let mut vec: SmallVec<i32, 0> = SmallVec::new();
+            vec.push(1);
+            assert!(vec.spilled());
+            assert_eq!(vec.as_slice(), &[1]);
+        }
+
+        #[test]
+        fn test_iterators() {
+            let mut vec: SmallVec<i32, 2> = (0..5).collect();
+            for x in &mut vec {
+                *x += 1;
+            }
+            vec.sort_by(|a, b| b.cmp(a));
+            assert_eq!(vec.iter().copied().collect::<Vec<_>>(), vec![5, 4, 3, 2, 1]);
+
+            let inline: SmallVec<i32, 8> = (0..3).collect();
+            assert_eq!(inline.into_iter().rev().collect::<Vec<_>>(), vec![2, 1, 0]);
+            assert_eq!(vec.into_iter().len(), 5);
+        }
+
+        #[test]
+        fn test_drop_count() {
+            let count = Arc::new(AtomicUsize::new(0));
+
+            {
+                let mut vec: SmallVec<DropCounter, 2> = SmallVec::new();
+                for _ in 0..2 {
+                    vec.push(DropCounter {
+                        count: count.clone(),
+                    });
+                }
+            }
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+
+            {
+                let mut vec: SmallVec<DropCounter, 2> = SmallVec::new();
+                for _ in 0..5 {
+                    vec.push(DropCounter {
+                        count: count.clone(),
+                    });
+                }
+                // ヒープへ移すときに要素が drop されてはいけない
+                assert_eq!(count.load(Ordering::SeqCst), 2);
+
+                let mut iter = vec.into_iter();
+                drop(iter.next());
+                assert_eq!(count.load(Ordering::SeqCst), 3);
+            }
+            assert_eq!(count.load(Ordering::SeqCst), 7);
+        }
+    }
+}
@@ -5,11 +5,392 @@
 // ---------------------------------------------------------
 
 pub mod my_vec {
-    use std::alloc::{alloc, dealloc, Layout};
+    use std::alloc::{alloc, dealloc, handle_alloc_error, realloc, Layout};
+    use std::cell::Cell;
+    use std::cmp::{self, Ordering};
+    use std::fmt;
+    use std::hash::{Hash, Hasher};
+    use std::marker::PhantomData;
+    use std::mem::{self, ManuallyDrop};
+    use std::ops::{Bound, Deref, DerefMut, Index, IndexMut, RangeBounds};
     use std::ptr::{self, NonNull};
+    use std::slice::{self, SliceIndex};
+
+    /// 容量の確保に失敗したことを表すエラー
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum TryReserveError {
+        /// 要素数が usize に収まらない、または確保サイズが `isize::MAX` バイトを超える
+        CapacityOverflow,
+        /// アロケータがメモリを確保できなかった
+        AllocError {
+            /// 確保しようとしたレイアウト
+            layout: Layout,
+        },
+    }
+
+    impl fmt::Display for TryReserveError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                TryReserveError::CapacityOverflow => write!(f, "capacity overflow"),
+                TryReserveError::AllocError { layout } => {
+                    write!(f, "memory allocation of {} bytes failed", layout.size())
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for TryReserveError {}
+
+    /// 確保に失敗したときのパニック用の補助関数
+    ///
+    /// 容量の溢れはパニック、アロケータの失敗は `handle_alloc_error` に回す。
+    fn handle_reserve(result: Result<(), TryReserveError>) {
+        match result {
+            Ok(()) => {}
+            Err(TryReserveError::CapacityOverflow) => panic!("capacity overflow"),
+            Err(TryReserveError::AllocError { layout }) => handle_alloc_error(layout),
+        }
+    }
+
+    /// アロケータがメモリを確保できなかったことを表すエラー
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct AllocError;
+
+    impl fmt::Display for AllocError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "memory allocation failed")
+        }
+    }
+
+    impl std::error::Error for AllocError {}
+
+    /// メモリアロケータのインターフェース
+    ///
+    /// 標準ライブラリの unstable な `std::alloc::Allocator` を模した安定版。
+    /// MyVec はサイズ 0 の Layout でこれらのメソッドを呼ばない。
+    ///
+    /// # Safety
+    /// allocate / grow / shrink が返すポインタは、指定した Layout のサイズとアラインメントを満たし、
+    /// deallocate されるまで有効でなければならない。
+    pub unsafe trait Allocator {
+        /// layout に従ってメモリを確保する
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+        /// allocate で確保したメモリを解放する
+        ///
+        /// # Safety
+        /// ptr はこのアロケータが layout で確保したものであること
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+        /// 確保済みの領域を new_layout の大きさに拡張する
+        ///
+        /// デフォルト実装は新しい領域を確保してコピーし、古い領域を解放する。
+        /// 失敗した場合、古い領域はそのまま有効。
+        ///
+        /// # Safety
+        /// ptr はこのアロケータが old_layout で確保したものであること。
+        /// new_layout は old_layout 以上のサイズで、アラインメントが同じであること。
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            let new_ptr = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+            Ok(new_ptr)
+        }
+
+        /// 確保済みの領域を new_layout の大きさに縮小する
+        ///
+        /// デフォルト実装は新しい領域を確保してコピーし、古い領域を解放する。
+        /// 失敗した場合、古い領域はそのまま有効。
+        ///
+        /// # Safety
+        /// ptr はこのアロケータが old_layout で確保したものであること。
+        /// new_layout は old_layout 以下のサイズで、アラインメントが同じであること。
+        unsafe fn shrink(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            let new_ptr = self.allocate(new_layout)?;
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_ptr.as_ptr(), new_layout.size());
+            self.deallocate(ptr, old_layout);
+            Ok(new_ptr)
+        }
+    }
+
+    // 参照経由でも使えるようにする（1 つのアリーナを複数の MyVec で共有する場合など）
+    unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+            (**self).allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            (**self).deallocate(ptr, layout)
+        }
+
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            (**self).grow(ptr, old_layout, new_layout)
+        }
+
+        unsafe fn shrink(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            (**self).shrink(ptr, old_layout, new_layout)
+        }
+    }
+
+    /// グローバルアロケータ（`std::alloc::alloc` など）を使うアロケータ
+    ///
+    /// MyVec のアロケータを省略した場合はこれが使われる。
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct Global;
+
+    unsafe impl Allocator for Global {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+            NonNull::new(unsafe { alloc(layout) }).ok_or(AllocError)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            dealloc(ptr.as_ptr(), layout)
+        }
+
+        // realloc は可能なら同じ場所で伸縮し、無理な場合だけ新しい領域へコピーして古い領域を解放する
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            NonNull::new(realloc(ptr.as_ptr(), old_layout, new_layout.size())).ok_or(AllocError)
+        }
+
+        unsafe fn shrink(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            NonNull::new(realloc(ptr.as_ptr(), old_layout, new_layout.size())).ok_or(AllocError)
+        }
+    }
+
+    /// 容量が足りなくなったときに、新しい容量をどう決めるか
+    ///
+    /// どの方針でも、新しい容量は必ず「必要な要素数」以上になる。
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum GrowthPolicy {
+        /// 2 倍にする（デフォルト）。push は償却 O(1)
+        #[default]
+        Doubling,
+        /// 1.5 倍にする。償却 O(1) のまま、使われない容量を減らせる
+        OneAndHalf,
+        /// 一定数ずつ増やす。再確保の回数が O(n) になるので push は償却 O(n)
+        FixedIncrement(usize),
+        /// 2 倍にしたうえで、確保バイト数を 2 のべき乗（アロケータのサイズクラス）に切り上げる
+        ///
+        /// 多くのアロケータはサイズクラス単位で確保するため、切り上げたぶんも無駄にならない。
+        SizeClass,
+    }
+
+    impl GrowthPolicy {
+        /// 容量 current から required 以上に拡張するときの新しい容量
+        ///
+        /// elem_size は要素 1 つのバイト数（SizeClass の計算に使う）。
+        pub fn next_capacity(&self, current: usize, required: usize, elem_size: usize) -> usize {
+            let grown = match *self {
+                GrowthPolicy::Doubling => current.saturating_mul(2),
+                GrowthPolicy::OneAndHalf => current.saturating_add(current / 2),
+                GrowthPolicy::FixedIncrement(n) => current.saturating_add(n.max(1)),
+                GrowthPolicy::SizeClass => {
+                    let target = cmp::max(current.saturating_mul(2), required);
+                    // バイト数を 2 のべき乗に切り上げ、そこに収まる要素数にする
+                    // （溢れる場合は切り上げずに target のまま）
+                    target
+                        .checked_mul(elem_size)
+                        .and_then(usize::checked_next_power_of_two)
+                        .map_or(target, |bytes| bytes / elem_size.max(1))
+                }
+            };
+            cmp::max(grown, required).max(1)
+        }
+    }
+
+    /// MyVec が容量の拡張方針を取り出す先
+    ///
+    /// MyVec の 3 つ目の型パラメータ G に使う。アロケータとは別にしてあるので、
+    /// どのアロケータとも組み合わせられる。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut vec = MyVec::with_growth_policy(GrowthPolicy::FixedIncrement(4));
+    /// vec.push(1);
+    /// assert_eq!(vec.capacity(), 4);
+    /// ```
+    pub trait GrowthSource {
+        /// 拡張方針
+        fn growth_policy(&self) -> GrowthPolicy;
+    }
+
+    /// 常に `GrowthPolicy::Doubling` を使う拡張方針（MyVec のデフォルト）
+    ///
+    /// サイズ 0 の型なので、デフォルトの MyVec の大きさは変わらない。
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct DefaultGrowth;
+
+    impl GrowthSource for DefaultGrowth {
+        fn growth_policy(&self) -> GrowthPolicy {
+            GrowthPolicy::Doubling
+        }
+    }
+
+    /// 構築時に選んだ方針をそのまま使う
+    impl GrowthSource for GrowthPolicy {
+        fn growth_policy(&self) -> GrowthPolicy {
+            *self
+        }
+    }
+
+    /// 再確保に関する統計（`StatsAllocator::stats` で取得する）
+    ///
+    /// 統計はアロケータ単位なので、1 つの `StatsAllocator` を複数の MyVec で共有すると
+    /// すべての MyVec の確保を合わせた値になる。
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct AllocStats {
+        /// バッファを確保・伸縮した回数（最初の確保も含む）
+        pub reallocations: usize,
+        /// 再確保でバッファの場所が変わり、中身のコピーが必要になったバイト数の合計
+        pub bytes_copied: usize,
+        /// このアロケータでこれまでに確保した最大のバッファのバイト数
+        ///
+        /// MyVec 1 つだけで使っている場合は、その MyVec の最大容量 × 要素のサイズになる。
+        pub largest_allocation: usize,
+    }
+
+    /// 再確保の統計を取るアロケータ
+    ///
+    /// 内側のアロケータ A に処理を任せつつ、確保・伸縮の回数とコピー量を数える。
+    /// 統計が必要なときだけ使えばよいので、普段の MyVec には計測のコストがかからない。
+    /// 参照 `&StatsAllocator` を MyVec に渡せば、MyVec を使いながら統計を読める。
+    ///
+    /// # Examples
+    /// ```
+    /// let stats = StatsAllocator::new();
+    /// let mut vec = MyVec::new_in(&stats);
+    /// for i in 0..1000u32 {
+    ///     vec.push(i);
+    /// }
+    /// assert_eq!(stats.stats().largest_allocation, 1024 * 4);
+    /// ```
+    #[derive(Debug, Default)]
+    pub struct StatsAllocator<A: Allocator = Global> {
+        alloc: A,
+        stats: Cell<AllocStats>,
+    }
+
+    impl StatsAllocator {
+        /// グローバルアロケータの統計を取る
+        pub fn new() -> Self {
+            Self::new_in(Global)
+        }
+    }
+
+    impl<A: Allocator> StatsAllocator<A> {
+        /// アロケータ alloc の統計を取る
+        pub fn new_in(alloc: A) -> Self {
+            StatsAllocator {
+                alloc,
+                stats: Cell::new(AllocStats::default()),
+            }
+        }
+
+        /// これまでの統計
+        pub fn stats(&self) -> AllocStats {
+            self.stats.get()
+        }
+
+        /// 統計を 0 に戻す
+        pub fn reset(&self) {
+            self.stats.set(AllocStats::default());
+        }
+
+        /// 確保・伸縮が成功したときに統計を更新する（内部関数）
+        ///
+        /// old は伸縮前の (ポインタ, コピーされうるバイト数)。新規確保なら None。
+        fn record(
+            &self,
+            result: &Result<NonNull<u8>, AllocError>,
+            old: Option<(NonNull<u8>, usize)>,
+            new_size: usize,
+        ) {
+            if let Ok(new_ptr) = result {
+                let mut stats = self.stats.get();
+                stats.reallocations += 1;
+                if let Some((old_ptr, copied)) = old {
+                    if old_ptr != *new_ptr {
+                        stats.bytes_copied += copied;
+                    }
+                }
+                stats.largest_allocation = cmp::max(stats.largest_allocation, new_size);
+                self.stats.set(stats);
+            }
+        }
+    }
+
+    unsafe impl<A: Allocator> Allocator for StatsAllocator<A> {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+            let result = self.alloc.allocate(layout);
+            self.record(&result, None, layout.size());
+            result
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.alloc.deallocate(ptr, layout)
+        }
+
+        unsafe fn grow(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            let result = self.alloc.grow(ptr, old_layout, new_layout);
+            self.record(&result, Some((ptr, old_layout.size())), new_layout.size());
+            result
+        }
+
+        unsafe fn shrink(
+            &self,
+            ptr: NonNull<u8>,
+            old_layout: Layout,
+            new_layout: Layout,
+        ) -> Result<NonNull<u8>, AllocError> {
+            let result = self.alloc.shrink(ptr, old_layout, new_layout);
+            self.record(&result, Some((ptr, new_layout.size())), new_layout.size());
+            result
+        }
+    }
 
     /// 動的配列の簡易実装
     ///
+    /// ゼロサイズ型（`()` など）の場合はメモリを一切確保せず、容量を `usize::MAX` として扱う。
+    ///
+    /// メモリの確保には型パラメータ A のアロケータを使う（省略時はグローバルアロケータ）。
+    /// `MyVec::new_in` でバンプアリーナや計測用のアロケータを指定できる。
+    /// 容量の拡張方針は型パラメータ G が決める（省略時は 2 倍。`GrowthSource` を参照）。
+    ///
     /// # Examples
     /// ```
     /// let mut vec = MyVec::new();
@@ -17,49 +398,114 @@ pub mod my_vec {
     /// vec.push(2);
     /// assert_eq!(vec.pop(), Some(2));
     /// ```
-    pub struct MyVec<T> {
+    pub struct MyVec<T, A: Allocator = Global, G: GrowthSource = DefaultGrowth> {
         ptr: NonNull<T>, // データへのポインタ（null でないことが保証される）
         len: usize,      // 現在の要素数
         capacity: usize, // 確保済みの容量
+        alloc: A,        // メモリの確保・解放に使うアロケータ
+        growth: G,       // 容量が足りないときの拡張方針
+        // T を所有していることをコンパイラに伝える（drop check と変性のため）
+        // NonNull<T> だけでは「T を指しているだけ」としか扱われない
+        _marker: PhantomData<T>,
     }
 
+    // NonNull<T> は Send / Sync ではないので、MyVec は自動では Send / Sync にならない。
+    // MyVec は T を排他的に所有しているだけなので、Vec<T> と同じ条件で安全に実装できる。
+
+    /// T と A（と G）が Send なら、MyVec ごと別スレッドへ移動できる
+    ///
+    /// `Rc` のように Send でない型を持つ MyVec はスレッドをまたげない
+    /// （テストの `Probe` で確認している）。
+    unsafe impl<T: Send, A: Allocator + Send, G: GrowthSource + Send> Send for MyVec<T, A, G> {}
+
+    /// T と A（と G）が Sync なら、&MyVec を複数スレッドで共有できる
+    unsafe impl<T: Sync, A: Allocator + Sync, G: GrowthSource + Sync> Sync for MyVec<T, A, G> {}
+
     impl<T> MyVec<T> {
         /// 新しい空のベクターを作成
         pub fn new() -> Self {
-            MyVec {
-                ptr: NonNull::dangling(), // 容量0の場合はダミーポインタ
-                len: 0,
-                capacity: 0,
-            }
+            Self::new_in(Global)
         }
 
         /// 指定した容量で新しいベクターを作成
         pub fn with_capacity(capacity: usize) -> Self {
-            if capacity == 0 {
-                return Self::new();
-            }
+            Self::with_capacity_in(capacity, Global)
+        }
+
+        /// 指定した容量で新しいベクターを作成（確保に失敗したらエラーを返す）
+        pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+            Self::try_with_capacity_in(capacity, Global)
+        }
+    }
+
+    impl<T> MyVec<T, Global, GrowthPolicy> {
+        /// 指定した拡張方針を使う空のベクターを作成
+        ///
+        /// `MyVec::with_growth_in(policy, Global)` の省略形。
+        pub fn with_growth_policy(policy: GrowthPolicy) -> Self {
+            Self::with_growth_in(policy, Global)
+        }
+    }
 
-            // 1. メモリレイアウト作成
-            let layout = Layout::array::<T>(capacity).expect("Failed to create layout");
+    impl<T, A: Allocator> MyVec<T, A> {
+        /// 指定したアロケータを使う空のベクターを作成
+        pub fn new_in(alloc: A) -> Self {
+            Self::with_growth_in(DefaultGrowth, alloc)
+        }
 
-            // 2. メモリ確保
-            let ptr = unsafe { alloc(layout) };
+        /// 指定したアロケータと容量で新しいベクターを作成
+        pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+            let mut vec = Self::new_in(alloc);
+            handle_reserve(vec.resize_buffer(capacity));
+            vec
+        }
 
-            // 3. null チェック
-            if ptr.is_null() {
-                panic!("Memory allocation failed");
-            }
+        /// 指定したアロケータと容量で新しいベクターを作成（確保に失敗したらエラーを返す）
+        pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+            let mut vec = Self::new_in(alloc);
+            vec.resize_buffer(capacity)?;
+            Ok(vec)
+        }
+    }
 
-            // 4. 型付きポインタに変換（NonNull で wrap）
-            let ptr = unsafe { NonNull::new_unchecked(ptr as *mut T) };
+    impl<T, A: Allocator, G: GrowthSource> MyVec<T, A, G> {
+        /// T がゼロサイズ型かどうか
+        ///
+        /// ゼロサイズ型はメモリを必要としないため、確保も解放もしない。
+        /// （サイズ 0 の Layout で alloc を呼ぶのは未定義動作）
+        const IS_ZST: bool = mem::size_of::<T>() == 0;
 
+        /// 指定した拡張方針とアロケータを使う空のベクターを作成
+        ///
+        /// # Examples
+        /// ```
+        /// let stats = StatsAllocator::new();
+        /// let mut vec = MyVec::with_growth_in(GrowthPolicy::OneAndHalf, &stats);
+        /// vec.extend(0..10);
+        /// assert_eq!(vec.growth_policy(), GrowthPolicy::OneAndHalf);
+        /// ```
+        pub fn with_growth_in(growth: G, alloc: A) -> Self {
             MyVec {
-                ptr,
+                ptr: NonNull::dangling(), // 容量0の場合はダミーポインタ
                 len: 0,
-                capacity,
+                // ゼロサイズ型はいくつ積んでもメモリを使わないので、最初から容量は無限扱い
+                capacity: if Self::IS_ZST { usize::MAX } else { 0 },
+                alloc,
+                growth,
+                _marker: PhantomData,
             }
         }
 
+        /// 使用しているアロケータへの参照
+        pub fn allocator(&self) -> &A {
+            &self.alloc
+        }
+
+        /// 使用している拡張方針
+        pub fn growth_policy(&self) -> GrowthPolicy {
+            self.growth.growth_policy()
+        }
+
         /// 要素を末尾に追加
         pub fn push(&mut self, value: T) {
             // 1. 容量が足りない場合は grow() を呼ぶ
@@ -76,6 +522,29 @@ pub mod my_vec {
             self.len += 1;
         }
 
+        /// 要素を末尾に追加（確保に失敗したら値とエラーを返す）
+        ///
+        /// # Examples
+        /// ```
+        /// let mut vec = MyVec::new();
+        /// if let Err((value, e)) = vec.try_push(1) {
+        ///     eprintln!("could not push {}: {}", value, e);
+        /// }
+        /// ```
+        pub fn try_push(&mut self, value: T) -> Result<(), (T, TryReserveError)> {
+            if self.len == self.capacity {
+                if let Err(e) = self.grow_amortized(1) {
+                    return Err((value, e));
+                }
+            }
+
+            unsafe {
+                ptr::write(self.ptr.as_ptr().add(self.len), value);
+            }
+            self.len += 1;
+            Ok(())
+        }
+
         /// 末尾の要素を削除して返す
         pub fn pop(&mut self) -> Option<T> {
             if self.len == 0 {
@@ -118,490 +587,8482 @@ pub mod my_vec {
             self.len == 0
         }
 
-        /// 容量を拡張（内部関数）
-        fn grow(&mut self) {
-            // 1. 新しい容量 = max(capacity * 2, 1)
-            let new_capacity = if self.capacity == 0 {
-                1
-            } else {
-                self.capacity * 2
+        /// index の位置に要素を挿入し、それ以降の要素を 1 つ後ろにずらす
+        ///
+        /// # 計算量
+        /// O(n - index)
+        ///
+        /// # Panics
+        /// index > len の場合
+        pub fn insert(&mut self, index: usize, value: T) {
+            assert!(
+                index <= self.len,
+                "insertion index (is {}) should be <= len (is {})",
+                index,
+                self.len
+            );
+            if self.len == self.capacity {
+                self.grow();
+            }
+
+            unsafe {
+                let p = self.ptr.as_ptr().add(index);
+                // 1. [index, len) を 1 つ後ろへ（領域が重なるので copy を使う）
+                ptr::copy(p, p.add(1), self.len - index);
+                // 2. 空いた位置に書き込む
+                ptr::write(p, value);
+            }
+            self.len += 1;
+        }
+
+        /// index の要素を取り除いて返し、それ以降の要素を 1 つ前に詰める
+        ///
+        /// # 計算量
+        /// O(n - index)
+        ///
+        /// # Panics
+        /// index >= len の場合
+        pub fn remove(&mut self, index: usize) -> T {
+            assert!(
+                index < self.len,
+                "removal index (is {}) should be < len (is {})",
+                index,
+                self.len
+            );
+
+            unsafe {
+                let p = self.ptr.as_ptr().add(index);
+                let value = ptr::read(p);
+                ptr::copy(p.add(1), p, self.len - index - 1);
+                self.len -= 1;
+                value
+            }
+        }
+
+        /// index の要素を取り除いて返し、空いた位置に末尾の要素を移す
+        ///
+        /// 要素の順序は保たれないが O(1) で削除できる。
+        ///
+        /// # Panics
+        /// index >= len の場合
+        pub fn swap_remove(&mut self, index: usize) -> T {
+            assert!(
+                index < self.len,
+                "swap_remove index (is {}) should be < len (is {})",
+                index,
+                self.len
+            );
+
+            unsafe {
+                let base = self.ptr.as_ptr();
+                let value = ptr::read(base.add(index));
+                // index が末尾の場合は同じ位置へのコピーになるので copy を使う
+                ptr::copy(base.add(self.len - 1), base.add(index), 1);
+                self.len -= 1;
+                value
+            }
+        }
+
+        /// 先頭 len 個だけを残し、残りを drop する
+        ///
+        /// len が現在の要素数以上の場合は何もしない。容量は変わらない。
+        pub fn truncate(&mut self, len: usize) {
+            if len >= self.len {
+                return;
+            }
+            let remaining = self.len - len;
+            unsafe {
+                let tail = ptr::slice_from_raw_parts_mut(self.ptr.as_ptr().add(len), remaining);
+                // drop 中にパニックしても二重 drop しないよう、先に len を更新する
+                self.len = len;
+                ptr::drop_in_place(tail);
+            }
+        }
+
+        /// 全要素を drop する（容量は変わらない）
+        pub fn clear(&mut self) {
+            self.truncate(0);
+        }
+
+        /// f が true を返す要素だけを残す（順序は保たれる）
+        ///
+        /// # Examples
+        /// ```
+        /// let mut vec = MyVec::new();
+        /// for i in 0..6 {
+        ///     vec.push(i);
+        /// }
+        /// vec.retain(|x| x % 2 == 0);
+        /// assert_eq!(vec.as_slice(), &[0, 2, 4]);
+        /// ```
+        pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+            let original_len = self.len;
+            // f や drop がパニックしても未処理の要素が見えないよう、len は guard が戻す
+            self.len = 0;
+            let mut g = CompactGuard {
+                vec: self,
+                read: 0,
+                write: 0,
+                original_len,
             };
-            // 2. 新しいメモリを確保
-            let layout = Layout::array::<T>(new_capacity).expect("Failed to Create layout");
-            let new_ptr = unsafe { alloc(layout) };
-            if new_ptr.is_null() {
-                panic!("Could not allocate");
-            }
-            let new_ptr = unsafe { NonNull::new_unchecked(new_ptr as *mut T) };
-            // 3. 古いデータをコピー
-            unsafe { ptr::copy_nonoverlapping(self.ptr.as_ptr(), new_ptr.as_ptr(), self.len) };
-
-            // 4. 古いメモリを解放
-            if self.capacity > 0 {
-                let old_layout = Layout::array::<T>(self.capacity).unwrap();
+
+            while g.read < original_len {
                 unsafe {
-                    dealloc(self.ptr.as_ptr() as *mut u8, old_layout);
+                    let cur = g.vec.ptr.as_ptr().add(g.read);
+                    if f(&*cur) {
+                        if g.read != g.write {
+                            ptr::copy_nonoverlapping(cur, g.vec.ptr.as_ptr().add(g.write), 1);
+                        }
+                        g.write += 1;
+                        g.read += 1;
+                    } else {
+                        // 先に read を進めておけば、drop がパニックしても二重 drop にならない
+                        g.read += 1;
+                        ptr::drop_in_place(cur);
+                    }
                 }
             }
-
-            self.ptr = new_ptr;
-            self.capacity = new_capacity;
         }
-    }
 
-    impl<T> Drop for MyVec<T> {
-        fn drop(&mut self) {
-            if self.capacity == 0 {
+        /// 連続する要素のうち、same_bucket が true を返すものを取り除く
+        ///
+        /// same_bucket(a, b) の a は判定中の要素、b は直前に残した要素。
+        pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+            let original_len = self.len;
+            if original_len <= 1 {
                 return;
             }
-            // 1. 各要素に対して drop を呼ぶ
-            for i in 0..self.len {
+            self.len = 0;
+            // 先頭要素は必ず残る
+            let mut g = CompactGuard {
+                vec: self,
+                read: 1,
+                write: 1,
+                original_len,
+            };
+
+            while g.read < original_len {
                 unsafe {
-                    let idx = self.ptr.as_ptr().add(i);
-                    ptr::drop_in_place(idx);
+                    let base = g.vec.ptr.as_ptr();
+                    let cur = base.add(g.read);
+                    let prev = base.add(g.write - 1);
+                    if same_bucket(&mut *cur, &mut *prev) {
+                        g.read += 1;
+                        ptr::drop_in_place(cur);
+                    } else {
+                        if g.read != g.write {
+                            ptr::copy_nonoverlapping(cur, base.add(g.write), 1);
+                        }
+                        g.write += 1;
+                        g.read += 1;
+                    }
                 }
             }
-            // 2. メモリを解放
-            unsafe {
-                let layout = Layout::array::<T>(self.capacity).unwrap();
-                dealloc(self.ptr.as_ptr() as *mut u8, layout);
-            }
         }
-    }
-
-    #[cfg(test)]
-    mod tests {
-        use super::*;
 
-        #[test]
-        fn test_new() {
-            let vec: MyVec<i32> = MyVec::new();
-            assert_eq!(vec.len(), 0);
-            assert_eq!(vec.capacity(), 0);
+        /// 連続する要素のうち、key が同じものを取り除く
+        ///
+        /// # Examples
+        /// ```
+        /// let mut vec = MyVec::new();
+        /// for x in [10, 11, 20, 21, 22, 30] {
+        ///     vec.push(x);
+        /// }
+        /// vec.dedup_by_key(|x| *x / 10);
+        /// assert_eq!(vec.as_slice(), &[10, 20, 30]);
+        /// ```
+        pub fn dedup_by_key<K, F>(&mut self, mut key: F)
+        where
+            K: PartialEq,
+            F: FnMut(&mut T) -> K,
+        {
+            self.dedup_by(|a, b| key(a) == key(b));
         }
 
-        #[test]
-        fn test_with_capacity() {
-            let vec: MyVec<usize> = MyVec::with_capacity(8);
-            assert_eq!(vec.capacity, 8);
-            assert_eq!(vec.capacity(), 8);
+        /// 全要素をスライスとして取得
+        pub fn as_slice(&self) -> &[T] {
+            // len 個の初期化済み要素が ptr から連続して並んでいる
+            // （len == 0 のときは dangling ポインタだが、空スライスなので問題ない）
+            unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
         }
 
-        #[test]
-        fn test_push_and_pop() {
-            let mut vec = MyVec::new();
-            vec.push(1);
-            vec.push(2);
-            vec.push(3);
+        /// 全要素を可変スライスとして取得
+        pub fn as_mut_slice(&mut self) -> &mut [T] {
+            unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+        }
 
-            assert_eq!(vec.len(), 3);
-            assert_eq!(vec.pop(), Some(3));
-            assert_eq!(vec.pop(), Some(2));
-            assert_eq!(vec.len(), 1);
+        /// バッファ先頭への生ポインタ
+        pub fn as_ptr(&self) -> *const T {
+            self.ptr.as_ptr()
         }
 
-        #[test]
-        fn test_capacity_growth() {
-            let mut vec = MyVec::new();
+        /// バッファ先頭への可変な生ポインタ
+        pub fn as_mut_ptr(&mut self) -> *mut T {
+            self.ptr.as_ptr()
+        }
 
-            for i in 0..10 {
-                vec.push(i);
+        /// 先頭から順に要素への参照を返すイテレータ
+        pub fn iter(&self) -> Iter<'_, T> {
+            Iter {
+                ptr: self.ptr,
+                start: 0,
+                end: self.len,
+                _marker: PhantomData,
             }
+        }
 
-            assert!(vec.capacity() >= 10);
-            assert_eq!(vec.len(), 10);
+        /// 先頭から順に要素への可変参照を返すイテレータ
+        pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+            IterMut {
+                ptr: self.ptr,
+                start: 0,
+                end: self.len,
+                _marker: PhantomData,
+            }
         }
-        #[test]
-        fn test_get() {
-            let mut vec = MyVec::new();
-            for i in 0..10 {
-                vec.push(i + 1);
+
+        /// 指定範囲の要素を取り除き、それらを所有権ごと返すイテレータ
+        ///
+        /// 範囲より後ろの要素は Drain が drop されたときに前詰めされる。
+        /// Drain が `mem::forget` でリークされた場合でも、ベクターは
+        /// 範囲の先頭までの要素だけを持つ一貫した状態になる。
+        ///
+        /// # Panics
+        /// 範囲の開始が終了より大きい場合、または終了が len を超える場合
+        ///
+        /// # Examples
+        /// ```
+        /// let mut vec = MyVec::new();
+        /// for i in 0..5 {
+        ///     vec.push(i);
+        /// }
+        /// let drained: Vec<i32> = vec.drain(1..3).collect();
+        /// assert_eq!(drained, vec![1, 2]);
+        /// assert_eq!(vec.len(), 3);
+        /// ```
+        pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, A, G> {
+            let start = match range.start_bound() {
+                Bound::Included(&n) => n,
+                // std の slice::range と同じく、usize::MAX を超える境界はパニックにする
+                Bound::Excluded(&n) => n
+                    .checked_add(1)
+                    .expect("attempted to index slice from after maximum usize"),
+                Bound::Unbounded => 0,
+            };
+            let end = match range.end_bound() {
+                Bound::Included(&n) => n
+                    .checked_add(1)
+                    .expect("attempted to index slice up to maximum usize"),
+                Bound::Excluded(&n) => n,
+                Bound::Unbounded => self.len,
+            };
+            assert!(start <= end, "drain start ({}) > end ({})", start, end);
+            assert!(end <= self.len, "drain end ({}) > len ({})", end, self.len);
+
+            let tail_len = self.len - end;
+            // 1. 先に len を範囲の先頭まで縮めておく（リーク対策）
+            //    Drain が forget されても、範囲内と後ろの要素が見えなくなるだけで二重 drop は起きない
+            self.len = start;
+
+            Drain {
+                vec: self,
+                start,
+                end,
+                tail_start: end,
+                tail_len,
             }
+        }
 
-            assert_eq!(vec.get(0), Some(&1));
-            assert_eq!(vec.get(9), Some(&10));
+        /// 少なくとも additional 個の要素を追加できるよう容量を確保する
+        ///
+        /// 再確保が必要な場合は倍々で増やすため、繰り返し呼んでも償却 O(1) になる。
+        ///
+        /// # Panics
+        /// 必要な容量が `isize::MAX` バイトを超える場合
+        pub fn reserve(&mut self, additional: usize) {
+            handle_reserve(self.try_reserve(additional));
         }
 
-        #[test]
-        fn test_drop_with_many_elements() {
-            // 大量の要素でメモリリークがないか確認
-            for _ in 0..100 {
-                let mut vec = MyVec::new();
-                for i in 0..100 {
-                    vec.push(format!("String {}", i));
-                }
-                // スコープを抜けるときに全要素がdropされる
-            }
+        /// ちょうど additional 個の要素を追加できるだけの容量を確保する
+        ///
+        /// # Panics
+        /// 必要な容量が `isize::MAX` バイトを超える場合
+        pub fn reserve_exact(&mut self, additional: usize) {
+            handle_reserve(self.try_reserve_exact(additional));
         }
 
+        /// `reserve` の失敗時にエラーを返す版
+        pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+            if self.capacity - self.len >= additional {
+                return Ok(());
+            }
+            self.grow_amortized(additional)
+        }
+
+        /// `reserve_exact` の失敗時にエラーを返す版
+        pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+            if self.capacity - self.len >= additional {
+                return Ok(());
+            }
+            let required = self
+                .len
+                .checked_add(additional)
+                .ok_or(TryReserveError::CapacityOverflow)?;
+            self.resize_buffer(required)
+        }
+
+        /// 容量を len まで縮める
+        pub fn shrink_to_fit(&mut self) {
+            self.shrink_to(0);
+        }
+
+        /// 容量を max(len, min_capacity) まで縮める
+        ///
+        /// 現在の容量がそれより小さい場合は何もしない。
+        pub fn shrink_to(&mut self, min_capacity: usize) {
+            let new_capacity = cmp::max(self.len, min_capacity);
+            if self.capacity > new_capacity {
+                // 縮小ではサイズが増えないので、容量の計算が溢れることはない
+                handle_reserve(self.resize_buffer(new_capacity));
+            }
+        }
+
+        /// 容量を拡張（内部関数）
+        fn grow(&mut self) {
+            // ゼロサイズ型の容量は usize::MAX なので、ゼロサイズ型でここに来るのは len が溢れるときだけ
+            handle_reserve(self.grow_amortized(1));
+        }
+
+        /// len + additional 以上の容量に拡張する（内部関数）
+        fn grow_amortized(&mut self, additional: usize) -> Result<(), TryReserveError> {
+            // 1. 必要な容量 = len + additional（溢れたらエラー）
+            let required = self
+                .len
+                .checked_add(additional)
+                .ok_or(TryReserveError::CapacityOverflow)?;
+            // 2. 新しい容量を拡張方針に従って決める（Doubling なら max(capacity * 2, required, 1)）
+            let new_capacity = self.growth.growth_policy().next_capacity(
+                self.capacity,
+                required,
+                mem::size_of::<T>(),
+            );
+            // 3. バッファを付け替える
+            self.resize_buffer(new_capacity)
+        }
+
+        /// バッファの容量を new_capacity に変更する（内部関数）
+        ///
+        /// 既存の要素はアロケータの grow / shrink によって引き継がれる。new_capacity は len 以上であること。
+        fn resize_buffer(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+            debug_assert!(new_capacity >= self.len);
+            if Self::IS_ZST || new_capacity == self.capacity {
+                return Ok(());
+            }
+
+            // 1. 新しいメモリレイアウト作成（isize::MAX バイトを超える場合はエラー）
+            let new_layout =
+                Layout::array::<T>(new_capacity).map_err(|_| TryReserveError::CapacityOverflow)?;
+
+            // 2. 容量 0 にする場合は解放するだけ
+            if new_capacity == 0 {
+                unsafe {
+                    let old_layout = Layout::array::<T>(self.capacity).unwrap();
+                    self.alloc.deallocate(self.ptr.cast(), old_layout);
+                }
+                self.ptr = NonNull::dangling();
+                self.capacity = 0;
+                return Ok(());
+            }
+
+            // 3. 新規確保、またはアロケータの grow / shrink で伸縮
+            let result = if self.capacity == 0 {
+                self.alloc.allocate(new_layout)
+            } else {
+                let old_layout = Layout::array::<T>(self.capacity).unwrap();
+                unsafe {
+                    if new_capacity > self.capacity {
+                        self.alloc.grow(self.ptr.cast(), old_layout, new_layout)
+                    } else {
+                        self.alloc.shrink(self.ptr.cast(), old_layout, new_layout)
+                    }
+                }
+            };
+
+            // 4. 失敗チェック（失敗時は古いバッファがそのまま残っている）
+            let new_ptr: NonNull<T> = result
+                .map_err(|_| TryReserveError::AllocError { layout: new_layout })?
+                .cast();
+
+            self.ptr = new_ptr;
+            self.capacity = new_capacity;
+            Ok(())
+        }
+    }
+
+    impl<T: PartialEq, A: Allocator, G: GrowthSource> MyVec<T, A, G> {
+        /// 連続する重複要素を取り除く
+        pub fn dedup(&mut self) {
+            self.dedup_by(|a, b| a == b);
+        }
+    }
+
+    /// retain / dedup_by 用の補助構造体
+    ///
+    /// [0, write) が残す要素、[read, original_len) が未処理の要素。
+    /// 処理の途中でパニックしても、drop 時に未処理の要素を前詰めして len を確定させる。
+    struct CompactGuard<'a, T, A: Allocator, G: GrowthSource> {
+        vec: &'a mut MyVec<T, A, G>,
+        read: usize,
+        write: usize,
+        original_len: usize,
+    }
+
+    impl<'a, T, A: Allocator, G: GrowthSource> Drop for CompactGuard<'a, T, A, G> {
+        fn drop(&mut self) {
+            let tail = self.original_len - self.read;
+            if self.read != self.write && tail > 0 {
+                unsafe {
+                    let base = self.vec.ptr.as_ptr();
+                    ptr::copy(base.add(self.read), base.add(self.write), tail);
+                }
+            }
+            self.vec.len = self.write + tail;
+        }
+    }
+
+    impl<T, A: Allocator, G: GrowthSource> Drop for MyVec<T, A, G> {
+        fn drop(&mut self) {
+            // 1. 各要素に対して drop を呼ぶ（ゼロサイズ型でも drop は要素ごとに必要）
+            for i in 0..self.len {
+                unsafe {
+                    let idx = self.ptr.as_ptr().add(i);
+                    ptr::drop_in_place(idx);
+                }
+            }
+            if Self::IS_ZST || self.capacity == 0 {
+                return;
+            }
+            // 2. メモリを解放
+            unsafe {
+                let layout = Layout::array::<T>(self.capacity).unwrap();
+                self.alloc.deallocate(self.ptr.cast(), layout);
+            }
+        }
+    }
+
+    // スライスへの Deref により、sort / binary_search / windows などのスライス API がそのまま使える
+    impl<T, A: Allocator, G: GrowthSource> Deref for MyVec<T, A, G> {
+        type Target = [T];
+
+        fn deref(&self) -> &[T] {
+            self.as_slice()
+        }
+    }
+
+    impl<T, A: Allocator, G: GrowthSource> DerefMut for MyVec<T, A, G> {
+        fn deref_mut(&mut self) -> &mut [T] {
+            self.as_mut_slice()
+        }
+    }
+
+    // usize と各種範囲型（1..3, ..2, 1.. など）の両方をスライスに委譲する
+    impl<T, I: SliceIndex<[T]>, A: Allocator, G: GrowthSource> Index<I> for MyVec<T, A, G> {
+        type Output = I::Output;
+
+        fn index(&self, index: I) -> &Self::Output {
+            &self.as_slice()[index]
+        }
+    }
+
+    impl<T, I: SliceIndex<[T]>, A: Allocator, G: GrowthSource> IndexMut<I> for MyVec<T, A, G> {
+        fn index_mut(&mut self, index: I) -> &mut Self::Output {
+            &mut self.as_mut_slice()[index]
+        }
+    }
+
+    impl<T: Clone, A: Allocator + Clone, G: GrowthSource + Clone> Clone for MyVec<T, A, G> {
+        /// 全要素を clone した新しいベクターを作る
+        ///
+        /// 途中で `T::clone` がパニックしても、push 済みの要素だけが
+        /// 新しいベクターの drop で解放されるので二重 drop は起きない。
+        fn clone(&self) -> Self {
+            let mut vec = MyVec::with_growth_in(self.growth.clone(), self.alloc.clone());
+            vec.reserve_exact(self.len);
+            for item in self.iter() {
+                vec.push(item.clone());
+            }
+            vec
+        }
+    }
+
+    impl<T: fmt::Debug, A: Allocator, G: GrowthSource> fmt::Debug for MyVec<T, A, G> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    impl<T, A: Allocator + Default, G: GrowthSource + Default> Default for MyVec<T, A, G> {
+        fn default() -> Self {
+            MyVec::with_growth_in(G::default(), A::default())
+        }
+    }
+
+    // 比較・ハッシュはすべてスライスに委譲する（Vec と同じ結果になる）
+    impl<T, U, A1, A2, G1, G2> PartialEq<MyVec<U, A2, G2>> for MyVec<T, A1, G1>
+    where
+        T: PartialEq<U>,
+        A1: Allocator,
+        A2: Allocator,
+        G1: GrowthSource,
+        G2: GrowthSource,
+    {
+        fn eq(&self, other: &MyVec<U, A2, G2>) -> bool {
+            self.as_slice() == other.as_slice()
+        }
+    }
+
+    impl<T: PartialEq<U>, U, A: Allocator, G: GrowthSource> PartialEq<[U]> for MyVec<T, A, G> {
+        fn eq(&self, other: &[U]) -> bool {
+            self.as_slice() == other
+        }
+    }
+
+    impl<T: PartialEq<U>, U, A: Allocator, G: GrowthSource> PartialEq<&[U]> for MyVec<T, A, G> {
+        fn eq(&self, other: &&[U]) -> bool {
+            self.as_slice() == *other
+        }
+    }
+
+    impl<T: PartialEq<U>, U, A: Allocator, G: GrowthSource, const N: usize> PartialEq<[U; N]>
+        for MyVec<T, A, G>
+    {
+        fn eq(&self, other: &[U; N]) -> bool {
+            self.as_slice() == other
+        }
+    }
+
+    impl<T: PartialEq<U>, U, A: Allocator, G: GrowthSource> PartialEq<Vec<U>> for MyVec<T, A, G> {
+        fn eq(&self, other: &Vec<U>) -> bool {
+            self.as_slice() == other.as_slice()
+        }
+    }
+
+    impl<T: Eq, A: Allocator, G: GrowthSource> Eq for MyVec<T, A, G> {}
+
+    impl<T: PartialOrd, A: Allocator, G: GrowthSource> PartialOrd for MyVec<T, A, G> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            self.as_slice().partial_cmp(other.as_slice())
+        }
+    }
+
+    impl<T: Ord, A: Allocator, G: GrowthSource> Ord for MyVec<T, A, G> {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.as_slice().cmp(other.as_slice())
+        }
+    }
+
+    impl<T: Hash, A: Allocator, G: GrowthSource> Hash for MyVec<T, A, G> {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.as_slice().hash(state)
+        }
+    }
+
+    impl<T> FromIterator<T> for MyVec<T> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            let mut vec = MyVec::new();
+            vec.extend(iter);
+            vec
+        }
+    }
+
+    impl<T, A: Allocator, G: GrowthSource> Extend<T> for MyVec<T, A, G> {
+        fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+            let iter = iter.into_iter();
+            // 下限のぶんだけ先に確保しておき、再確保の回数を減らす
+            self.reserve(iter.size_hint().0);
+            for item in iter {
+                self.push(item);
+            }
+        }
+    }
+
+    impl<'a, T: Copy + 'a, A: Allocator, G: GrowthSource> Extend<&'a T> for MyVec<T, A, G> {
+        fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+            self.extend(iter.into_iter().copied());
+        }
+    }
+
+    impl<T> From<Vec<T>> for MyVec<T> {
+        /// Vec のバッファをそのまま引き継ぐ（要素のコピーは発生しない）
+        ///
+        /// Vec もグローバルアロケータと `Layout::array::<T>(capacity)` で確保しているので、
+        /// MyVec の Drop でそのまま解放できる。
+        fn from(vec: Vec<T>) -> Self {
+            let mut vec = ManuallyDrop::new(vec);
+            MyVec {
+                // 空の Vec の as_mut_ptr も dangling な非 null ポインタを返す
+                ptr: NonNull::new(vec.as_mut_ptr()).unwrap(),
+                len: vec.len(),
+                capacity: if Self::IS_ZST {
+                    usize::MAX
+                } else {
+                    vec.capacity()
+                },
+                alloc: Global,
+                growth: DefaultGrowth,
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    impl<T, const N: usize> From<[T; N]> for MyVec<T> {
+        fn from(arr: [T; N]) -> Self {
+            let mut vec = MyVec::with_capacity(N);
+            for item in arr {
+                vec.push(item);
+            }
+            vec
+        }
+    }
+
+    /// MyVec の要素への参照を返すイテレータ
+    pub struct Iter<'a, T> {
+        ptr: NonNull<T>,
+        start: usize,
+        end: usize,
+        _marker: PhantomData<&'a T>,
+    }
+
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.start == self.end {
+                None
+            } else {
+                let item = unsafe { &*self.ptr.as_ptr().add(self.start) };
+                self.start += 1;
+                Some(item)
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = self.end - self.start;
+            (len, Some(len))
+        }
+    }
+
+    impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+        fn next_back(&mut self) -> Option<Self::Item> {
+            if self.start == self.end {
+                None
+            } else {
+                self.end -= 1;
+                Some(unsafe { &*self.ptr.as_ptr().add(self.end) })
+            }
+        }
+    }
+
+    impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+    // Iter は &T と同じ条件で Send / Sync になる
+    unsafe impl<'a, T: Sync> Send for Iter<'a, T> {}
+    unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+    /// MyVec の要素への可変参照を返すイテレータ
+    pub struct IterMut<'a, T> {
+        ptr: NonNull<T>,
+        start: usize,
+        end: usize,
+        _marker: PhantomData<&'a mut T>,
+    }
+
+    impl<'a, T> Iterator for IterMut<'a, T> {
+        type Item = &'a mut T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.start == self.end {
+                None
+            } else {
+                // 各インデックスは一度しか返さないので、可変参照が重複することはない
+                let item = unsafe { &mut *self.ptr.as_ptr().add(self.start) };
+                self.start += 1;
+                Some(item)
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = self.end - self.start;
+            (len, Some(len))
+        }
+    }
+
+    impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+        fn next_back(&mut self) -> Option<Self::Item> {
+            if self.start == self.end {
+                None
+            } else {
+                self.end -= 1;
+                Some(unsafe { &mut *self.ptr.as_ptr().add(self.end) })
+            }
+        }
+    }
+
+    impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+    // IterMut は &mut T と同じ条件で Send / Sync になる
+    unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
+    unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
+
+    /// MyVec を消費して要素を所有権ごと返すイテレータ
+    ///
+    /// 途中で drop された場合は、残りの要素を drop してからバッファを解放する。
+    pub struct IntoIter<T, A: Allocator = Global> {
+        buf: NonNull<T>, // 解放するバッファの先頭
+        capacity: usize,
+        start: usize, // 次に front から返す位置
+        end: usize,   // back から返す位置の 1 つ後ろ
+        alloc: A,
+        _marker: PhantomData<T>,
+    }
+
+    unsafe impl<T: Send, A: Allocator + Send> Send for IntoIter<T, A> {}
+    unsafe impl<T: Sync, A: Allocator + Sync> Sync for IntoIter<T, A> {}
+
+    impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            if self.start == self.end {
+                None
+            } else {
+                let item = unsafe { ptr::read(self.buf.as_ptr().add(self.start)) };
+                self.start += 1;
+                Some(item)
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = self.end - self.start;
+            (len, Some(len))
+        }
+    }
+
+    impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+        fn next_back(&mut self) -> Option<T> {
+            if self.start == self.end {
+                None
+            } else {
+                self.end -= 1;
+                Some(unsafe { ptr::read(self.buf.as_ptr().add(self.end)) })
+            }
+        }
+    }
+
+    impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+    impl<T, A: Allocator> Drop for IntoIter<T, A> {
+        fn drop(&mut self) {
+            // 1. まだ返していない要素を drop
+            for _ in &mut *self {}
+            // 2. バッファを解放
+            if !MyVec::<T, A>::IS_ZST && self.capacity > 0 {
+                unsafe {
+                    let layout = Layout::array::<T>(self.capacity).unwrap();
+                    self.alloc.deallocate(self.buf.cast(), layout);
+                }
+            }
+        }
+    }
+
+    /// `MyVec::drain` が返すイテレータ
+    pub struct Drain<'a, T, A: Allocator = Global, G: GrowthSource = DefaultGrowth> {
+        vec: &'a mut MyVec<T, A, G>,
+        start: usize,      // 次に front から返す位置
+        end: usize,        // back から返す位置の 1 つ後ろ
+        tail_start: usize, // 範囲より後ろに残る要素の開始位置
+        tail_len: usize,   // 範囲より後ろに残る要素数
+    }
+
+    impl<'a, T, A: Allocator, G: GrowthSource> Iterator for Drain<'a, T, A, G> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            if self.start == self.end {
+                None
+            } else {
+                let item = unsafe { ptr::read(self.vec.ptr.as_ptr().add(self.start)) };
+                self.start += 1;
+                Some(item)
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let len = self.end - self.start;
+            (len, Some(len))
+        }
+    }
+
+    impl<'a, T, A: Allocator, G: GrowthSource> DoubleEndedIterator for Drain<'a, T, A, G> {
+        fn next_back(&mut self) -> Option<T> {
+            if self.start == self.end {
+                None
+            } else {
+                self.end -= 1;
+                Some(unsafe { ptr::read(self.vec.ptr.as_ptr().add(self.end)) })
+            }
+        }
+    }
+
+    impl<'a, T, A: Allocator, G: GrowthSource> ExactSizeIterator for Drain<'a, T, A, G> {}
+
+    impl<'a, T, A: Allocator, G: GrowthSource> Drop for Drain<'a, T, A, G> {
+        fn drop(&mut self) {
+            // 1. 取り出されなかった範囲内の要素を drop
+            for _ in &mut *self {}
+
+            // 2. 後ろに残った要素を範囲の先頭（= 現在の len）まで前詰めする
+            let new_start = self.vec.len;
+            if self.tail_len > 0 {
+                unsafe {
+                    let base = self.vec.ptr.as_ptr();
+                    ptr::copy(
+                        base.add(self.tail_start),
+                        base.add(new_start),
+                        self.tail_len,
+                    );
+                }
+            }
+            self.vec.len = new_start + self.tail_len;
+        }
+    }
+
+    impl<T, A: Allocator, G: GrowthSource> IntoIterator for MyVec<T, A, G> {
+        type Item = T;
+        type IntoIter = IntoIter<T, A>;
+
+        fn into_iter(self) -> IntoIter<T, A> {
+            // MyVec 自身の Drop が走らないようにして、バッファとアロケータの所有権を IntoIter に移す
+            let vec = ManuallyDrop::new(self);
+            // 拡張方針は IntoIter では使わないので、ここで drop する
+            drop(unsafe { ptr::read(&vec.growth) });
+            IntoIter {
+                buf: vec.ptr,
+                capacity: vec.capacity,
+                start: 0,
+                end: vec.len,
+                alloc: unsafe { ptr::read(&vec.alloc) },
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    impl<'a, T, A: Allocator, G: GrowthSource> IntoIterator for &'a MyVec<T, A, G> {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+
+        fn into_iter(self) -> Iter<'a, T> {
+            self.iter()
+        }
+    }
+
+    impl<'a, T, A: Allocator, G: GrowthSource> IntoIterator for &'a mut MyVec<T, A, G> {
+        type Item = &'a mut T;
+        type IntoIter = IterMut<'a, T>;
+
+        fn into_iter(self) -> IterMut<'a, T> {
+            self.iter_mut()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::cell::{Cell, UnsafeCell};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        /// drop 回数を数えるテスト用の型
+        struct DropCounter {
+            count: Arc<AtomicUsize>,
+        }
+
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                self.count.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        /// DropCounter を n 個持つベクターを作る
+        fn counters(n: usize) -> (MyVec<DropCounter>, Arc<AtomicUsize>) {
+            let count = Arc::new(AtomicUsize::new(0));
+            let mut vec = MyVec::new();
+            for _ in 0..n {
+                vec.push(DropCounter {
+                    count: count.clone(),
+                });
+            }
+            (vec, count)
+        }
+
+        #[test]
+        fn test_new() {
+            let vec: MyVec<i32> = MyVec::new();
+            assert_eq!(vec.len(), 0);
+            assert_eq!(vec.capacity(), 0);
+        }
+
+        #[test]
+        fn test_with_capacity() {
+            let vec: MyVec<usize> = MyVec::with_capacity(8);
+            assert_eq!(vec.capacity, 8);
+            assert_eq!(vec.capacity(), 8);
+        }
+
+        #[test]
+        fn test_push_and_pop() {
+            let mut vec = MyVec::new();
+            vec.push(1);
+            vec.push(2);
+            vec.push(3);
+
+            assert_eq!(vec.len(), 3);
+            assert_eq!(vec.pop(), Some(3));
+            assert_eq!(vec.pop(), Some(2));
+            assert_eq!(vec.len(), 1);
+        }
+
+        #[test]
+        fn test_capacity_growth() {
+            let mut vec = MyVec::new();
+
+            for i in 0..10 {
+                vec.push(i);
+            }
+
+            assert!(vec.capacity() >= 10);
+            assert_eq!(vec.len(), 10);
+        }
+        #[test]
+        fn test_get() {
+            let mut vec = MyVec::new();
+            for i in 0..10 {
+                vec.push(i + 1);
+            }
+
+            assert_eq!(vec.get(0), Some(&1));
+            assert_eq!(vec.get(9), Some(&10));
+        }
+
+        #[test]
+        fn test_drop_with_many_elements() {
+            // 大量の要素でメモリリークがないか確認
+            for _ in 0..100 {
+                let mut vec = MyVec::new();
+                for i in 0..100 {
+                    vec.push(format!("String {}", i));
+                }
+                // スコープを抜けるときに全要素がdropされる
+            }
+        }
+
+        #[test]
+        fn test_drop_count() {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+            use std::sync::Arc;
+
+            // Dropが何回呼ばれたかカウントする型
+            struct DropCounter {
+                count: Arc<AtomicUsize>,
+            }
+
+            impl Drop for DropCounter {
+                fn drop(&mut self) {
+                    self.count.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
+            let drop_count = Arc::new(AtomicUsize::new(0));
+
+            {
+                let mut vec = MyVec::new();
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+            } // ここで5つのDropCounterがdropされるはず
+
+            // 5回dropが呼ばれたか確認
+            assert_eq!(drop_count.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn test_drop_after_pop() {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+            use std::sync::Arc;
+
+            struct DropCounter {
+                count: Arc<AtomicUsize>,
+            }
+
+            impl Drop for DropCounter {
+                fn drop(&mut self) {
+                    self.count.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
+            let drop_count = Arc::new(AtomicUsize::new(0));
+
+            {
+                let mut vec = MyVec::new();
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+                vec.push(DropCounter {
+                    count: drop_count.clone(),
+                });
+
+                // 1つpopする（この時点で1回dropされる）
+                let _popped = vec.pop();
+                drop(_popped); // 明示的にdrop
+
+                assert_eq!(drop_count.load(Ordering::SeqCst), 1);
+
+                // 残り2つはMyVecのdropで処理される
+            }
+
+            // 合計3回dropが呼ばれたか確認
+            assert_eq!(drop_count.load(Ordering::SeqCst), 3);
+        }
+
+        #[test]
+        fn test_drop_empty_vec() {
+            // 空のベクタのdropでパニックしないか確認
+            let vec: MyVec<i32> = MyVec::new();
+            drop(vec); // 明示的にdrop
+        }
+
+        #[test]
+        fn test_drop_with_capacity_but_no_elements() {
+            // 容量はあるが要素がない場合
+            let vec: MyVec<String> = MyVec::with_capacity(10);
+            drop(vec); // パニックしないはず
+        }
+
+        #[test]
+        fn test_iter() {
+            let mut vec = MyVec::new();
+            for i in 0..5 {
+                vec.push(i);
+            }
+
+            let collected: Vec<&i32> = vec.iter().collect();
+            assert_eq!(collected, vec![&0, &1, &2, &3, &4]);
+            assert_eq!(vec.iter().len(), 5);
+
+            let reversed: Vec<i32> = vec.iter().rev().copied().collect();
+            assert_eq!(reversed, vec![4, 3, 2, 1, 0]);
+
+            let mut sum = 0;
+            for x in &vec {
+                sum += x;
+            }
+            assert_eq!(sum, 10);
+        }
+
+        #[test]
+        fn test_iter_mut() {
+            let mut vec = MyVec::new();
+            for i in 0..5 {
+                vec.push(i);
+            }
+
+            for x in vec.iter_mut() {
+                *x *= 10;
+            }
+            for x in &mut vec {
+                *x += 1;
+            }
+
+            assert_eq!(vec.get(0), Some(&1));
+            assert_eq!(vec.get(4), Some(&41));
+        }
+
+        #[test]
+        fn test_into_iter() {
+            let mut vec = MyVec::new();
+            for i in 0..5 {
+                vec.push(format!("s{}", i));
+            }
+
+            let mut iter = vec.into_iter();
+            assert_eq!(iter.next(), Some("s0".to_string()));
+            assert_eq!(iter.next_back(), Some("s4".to_string()));
+            assert_eq!(iter.len(), 3);
+            let rest: Vec<String> = iter.collect();
+            assert_eq!(rest, vec!["s1", "s2", "s3"]);
+        }
+
+        #[test]
+        fn test_into_iter_drops_remaining() {
+            let (vec, count) = counters(5);
+
+            let mut iter = vec.into_iter();
+            drop(iter.next());
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+
+            // 消費されなかった 4 つは IntoIter の drop で処理される
+            drop(iter);
+            assert_eq!(count.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn test_drain_middle() {
+            let mut vec = MyVec::new();
+            for i in 0..6 {
+                vec.push(i);
+            }
+
+            let drained: Vec<i32> = vec.drain(1..4).collect();
+            assert_eq!(drained, vec![1, 2, 3]);
+            assert_eq!(vec.len(), 3);
+            let rest: Vec<i32> = vec.iter().copied().collect();
+            assert_eq!(rest, vec![0, 4, 5]);
+
+            // 全範囲
+            let drained: Vec<i32> = vec.drain(..).collect();
+            assert_eq!(drained, vec![0, 4, 5]);
+            assert!(vec.is_empty());
+        }
+
+        #[test]
+        fn test_drain_partially_consumed() {
+            let (mut vec, count) = counters(6);
+
+            {
+                let mut drain = vec.drain(2..=4);
+                drop(drain.next());
+                assert_eq!(count.load(Ordering::SeqCst), 1);
+            } // 残りの 2 つはここで drop される
+
+            assert_eq!(count.load(Ordering::SeqCst), 3);
+            assert_eq!(vec.len(), 3);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 6);
+        }
+
+        #[test]
+        fn test_drain_leaked() {
+            let (mut vec, count) = counters(5);
+
+            std::mem::forget(vec.drain(1..3));
+
+            // リークしても範囲の先頭までの要素だけが残り、二重 drop は起きない
+            assert_eq!(vec.len(), 1);
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 1);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_drain_out_of_bounds() {
+            let mut vec = MyVec::new();
+            vec.push(1);
+            vec.drain(0..2);
+        }
+
+        #[test]
+        #[should_panic(expected = "up to maximum usize")]
+        fn test_drain_inclusive_end_overflow() {
+            let mut vec = MyVec::new();
+            vec.push(1);
+            vec.drain(0..=usize::MAX);
+        }
+
+        #[test]
+        fn test_as_slice() {
+            let mut vec = MyVec::new();
+            assert_eq!(vec.as_slice(), &[] as &[i32]);
+
+            vec.push(1);
+            vec.push(2);
+            vec.push(3);
+            assert_eq!(vec.as_slice(), &[1, 2, 3]);
+
+            vec.as_mut_slice()[0] = 10;
+            assert_eq!(vec.as_slice(), &[10, 2, 3]);
+            assert_eq!(unsafe { *vec.as_ptr().add(1) }, 2);
+        }
+
+        #[test]
+        fn test_index() {
+            let mut vec = MyVec::new();
+            for i in 0..5 {
+                vec.push(i);
+            }
+
+            assert_eq!(vec[0], 0);
+            assert_eq!(vec[4], 4);
+            assert_eq!(&vec[1..3], &[1, 2]);
+            assert_eq!(&vec[..2], &[0, 1]);
+            assert_eq!(&vec[3..], &[3, 4]);
+            assert_eq!(&vec[..], &[0, 1, 2, 3, 4]);
+
+            vec[2] = 20;
+            vec[3..].copy_from_slice(&[30, 40]);
+            assert_eq!(vec.as_slice(), &[0, 1, 20, 30, 40]);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_index_out_of_bounds() {
+            let mut vec = MyVec::new();
+            vec.push(1);
+            let _ = vec[1];
+        }
+
+        #[test]
+        fn test_slice_methods_via_deref() {
+            let mut vec = MyVec::new();
+            for x in [5, 2, 8, 1, 9] {
+                vec.push(x);
+            }
+
+            vec.sort();
+            assert_eq!(vec.as_slice(), &[1, 2, 5, 8, 9]);
+            assert_eq!(vec.binary_search(&8), Ok(3));
+            assert_eq!(vec.windows(2).count(), 4);
+            assert_eq!(vec.chunks(2).count(), 3);
+            assert_eq!(vec.first(), Some(&1));
+            assert!(vec.contains(&5));
+        }
+
+        #[test]
+        fn test_insert() {
+            let mut vec = MyVec::new();
+            vec.insert(0, 2);
+            vec.insert(0, 0);
+            vec.insert(1, 1);
+            vec.insert(3, 3);
+
+            assert_eq!(vec.as_slice(), &[0, 1, 2, 3]);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_insert_out_of_bounds() {
+            let mut vec = MyVec::new();
+            vec.push(1);
+            vec.insert(2, 2);
+        }
+
+        #[test]
+        fn test_remove() {
+            let mut vec = MyVec::new();
+            for i in 0..5 {
+                vec.push(i);
+            }
+
+            assert_eq!(vec.remove(0), 0);
+            assert_eq!(vec.remove(1), 2);
+            assert_eq!(vec.remove(2), 4);
+            assert_eq!(vec.as_slice(), &[1, 3]);
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_remove_out_of_bounds() {
+            let mut vec: MyVec<i32> = MyVec::new();
+            vec.remove(0);
+        }
+
+        #[test]
+        fn test_swap_remove() {
+            let mut vec = MyVec::new();
+            for i in 0..5 {
+                vec.push(i);
+            }
+
+            assert_eq!(vec.swap_remove(1), 1);
+            assert_eq!(vec.as_slice(), &[0, 4, 2, 3]);
+            // 末尾の要素の削除
+            assert_eq!(vec.swap_remove(3), 3);
+            assert_eq!(vec.as_slice(), &[0, 4, 2]);
+        }
+
+        #[test]
+        fn test_insert_remove_drop_count() {
+            let (mut vec, count) = counters(4);
+            let extra = DropCounter {
+                count: count.clone(),
+            };
+
+            vec.insert(2, extra);
+            drop(vec.remove(0));
+            drop(vec.swap_remove(0));
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+            assert_eq!(vec.len(), 3);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn test_truncate_and_clear() {
+            let (mut vec, count) = counters(5);
+            let capacity = vec.capacity();
+
+            vec.truncate(10);
+            assert_eq!(vec.len(), 5);
+
+            vec.truncate(2);
+            assert_eq!(vec.len(), 2);
+            assert_eq!(count.load(Ordering::SeqCst), 3);
+
+            vec.clear();
+            assert!(vec.is_empty());
+            assert_eq!(vec.capacity(), capacity);
+            assert_eq!(count.load(Ordering::SeqCst), 5);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 5);
+        }
+
+        #[test]
+        fn test_retain() {
+            let mut vec = MyVec::new();
+            for i in 0..10 {
+                vec.push(i);
+            }
+
+            vec.retain(|x| x % 3 == 0);
+            assert_eq!(vec.as_slice(), &[0, 3, 6, 9]);
+
+            vec.retain(|_| true);
+            assert_eq!(vec.as_slice(), &[0, 3, 6, 9]);
+
+            vec.retain(|_| false);
+            assert!(vec.is_empty());
+        }
+
+        #[test]
+        fn test_retain_drop_count() {
+            let (mut vec, count) = counters(6);
+
+            let mut i = 0;
+            vec.retain(|_| {
+                i += 1;
+                i % 2 == 0
+            });
+            assert_eq!(vec.len(), 3);
+            assert_eq!(count.load(Ordering::SeqCst), 3);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 6);
+        }
+
+        #[test]
+        fn test_retain_panic_safety() {
+            use std::panic::{catch_unwind, AssertUnwindSafe};
+
+            let (mut vec, count) = counters(6);
+
+            let mut i = 0;
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                vec.retain(|_| {
+                    i += 1;
+                    if i == 4 {
+                        panic!("retain panic");
+                    }
+                    i % 2 == 0
+                });
+            }));
+            assert!(result.is_err());
+
+            // 1, 3 番目が削除され、残り 4 つ（判定済み 1 つ + 未処理 3 つ）が残る
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+            assert_eq!(vec.len(), 4);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 6);
+        }
+
+        #[test]
+        fn test_dedup() {
+            let mut vec = MyVec::new();
+            for x in [1, 1, 2, 3, 3, 3, 1, 4, 4] {
+                vec.push(x);
+            }
+
+            vec.dedup();
+            assert_eq!(vec.as_slice(), &[1, 2, 3, 1, 4]);
+        }
+
+        #[test]
+        fn test_dedup_by_key() {
+            let mut vec = MyVec::new();
+            for x in [10, 11, 20, 21, 22, 30, 12] {
+                vec.push(x);
+            }
+
+            vec.dedup_by_key(|x| *x / 10);
+            assert_eq!(vec.as_slice(), &[10, 20, 30, 12]);
+        }
+
+        #[test]
+        fn test_dedup_by_key_drop_count() {
+            let (mut vec, count) = counters(5);
+
+            // すべて同じ key なので先頭以外が削除される
+            vec.dedup_by_key(|_| 0);
+            assert_eq!(vec.len(), 1);
+            assert_eq!(count.load(Ordering::SeqCst), 4);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 5);
+        }
+
+        /// drop 回数を数えるゼロサイズ型
+        struct ZstDropCounter;
+
+        static ZST_DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        impl Drop for ZstDropCounter {
+            fn drop(&mut self) {
+                ZST_DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        #[test]
+        fn test_zst_basic() {
+            let mut vec = MyVec::new();
+            assert_eq!(vec.capacity(), usize::MAX);
+
+            for _ in 0..100 {
+                vec.push(());
+            }
+            assert_eq!(vec.len(), 100);
+            assert_eq!(vec.capacity(), usize::MAX);
+            assert_eq!(vec.get(99), Some(&()));
+            assert_eq!(vec.iter().count(), 100);
+
+            vec.insert(50, ());
+            vec.remove(0);
+            assert_eq!(vec.pop(), Some(()));
+            assert_eq!(vec.len(), 99);
+            assert_eq!(vec.drain(10..20).count(), 10);
+            assert_eq!(vec.into_iter().count(), 89);
+
+            let vec: MyVec<()> = MyVec::with_capacity(10);
+            assert_eq!(vec.capacity(), usize::MAX);
+        }
+
+        // ZST_DROPS を共有するので、ゼロサイズ型の drop 検証は 1 つのテストにまとめる
+        #[test]
+        fn test_zst_drop_count() {
+            ZST_DROPS.store(0, Ordering::SeqCst);
+
+            {
+                let mut vec = MyVec::new();
+                for _ in 0..10 {
+                    vec.push(ZstDropCounter);
+                }
+                drop(vec.pop());
+                assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 1);
+
+                vec.truncate(5);
+                assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 5);
+            } // 残り 5 つが drop される
+            assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 10);
+
+            {
+                let mut vec = MyVec::new();
+                for _ in 0..10 {
+                    vec.push(ZstDropCounter);
+                }
+                let mut iter = vec.into_iter();
+                drop(iter.next());
+            } // IntoIter の残り 9 つが drop される
+            assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 20);
+
+            {
+                let mut vec = MyVec::new();
+                for _ in 0..10 {
+                    vec.push(ZstDropCounter);
+                }
+                vec.retain(|_| false);
+                assert!(vec.is_empty());
+            }
+            assert_eq!(ZST_DROPS.load(Ordering::SeqCst), 30);
+        }
+
+        #[test]
+        fn test_growth_keeps_elements() {
+            let mut vec = MyVec::new();
+            for i in 0..1000 {
+                vec.push(format!("String {}", i));
+            }
+
+            assert_eq!(vec.len(), 1000);
+            assert_eq!(vec[0], "String 0");
+            assert_eq!(vec[999], "String 999");
+        }
+
+        #[test]
+        fn test_reserve() {
+            let mut vec: MyVec<i32> = MyVec::new();
+            vec.reserve(10);
+            assert!(vec.capacity() >= 10);
+
+            // 既に余裕がある場合は容量は変わらない
+            let capacity = vec.capacity();
+            vec.reserve(5);
+            assert_eq!(vec.capacity(), capacity);
+
+            for i in 0..10 {
+                vec.push(i);
+            }
+            // 倍々で増えるので、1 つ足りないだけでも容量は 2 倍になる
+            vec.reserve(1);
+            assert!(vec.capacity() >= 20);
+        }
+
+        #[test]
+        fn test_reserve_exact() {
+            let mut vec: MyVec<i32> = MyVec::new();
+            vec.push(1);
+            vec.reserve_exact(10);
+            assert_eq!(vec.capacity(), 11);
+            assert_eq!(vec[0], 1);
+        }
+
+        #[test]
+        fn test_shrink_to_fit() {
+            let (mut vec, count) = counters(10);
+            vec.reserve_exact(90);
+            assert_eq!(vec.capacity(), 100);
+
+            vec.shrink_to(20);
+            assert_eq!(vec.capacity(), 20);
+            // 現在の容量より大きい値を指定しても増えない
+            vec.shrink_to(50);
+            assert_eq!(vec.capacity(), 20);
+
+            vec.truncate(3);
+            vec.shrink_to_fit();
+            assert_eq!(vec.capacity(), 3);
+            assert_eq!(vec.len(), 3);
+
+            vec.clear();
+            vec.shrink_to_fit();
+            assert_eq!(vec.capacity(), 0);
+            vec.push(DropCounter {
+                count: count.clone(),
+            });
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 11);
+        }
+
+        #[test]
+        #[should_panic(expected = "capacity overflow")]
+        fn test_reserve_len_overflow() {
+            let mut vec = MyVec::new();
+            vec.push(1u8);
+            vec.reserve(usize::MAX);
+        }
+
+        #[test]
+        #[should_panic(expected = "capacity overflow")]
+        fn test_reserve_exceeds_isize_max() {
+            // 要素数は usize に収まるが、バイト数が isize::MAX を超える
+            let mut vec: MyVec<u64> = MyVec::new();
+            vec.reserve_exact(isize::MAX as usize / 4);
+        }
+
+        #[test]
+        #[should_panic(expected = "capacity overflow")]
+        fn test_with_capacity_overflow() {
+            let _vec: MyVec<u32> = MyVec::with_capacity(usize::MAX / 2);
+        }
+
+        #[test]
+        #[should_panic(expected = "capacity overflow")]
+        fn test_zst_len_overflow() {
+            // usize::MAX 回の drop ループを避けるため ManuallyDrop で包む（ゼロサイズ型なのでリークはない）
+            let mut vec = ManuallyDrop::new(MyVec::new());
+            vec.push(());
+            // ゼロサイズ型なので len を直接書き換えても読み書きするメモリはない
+            vec.len = usize::MAX;
+            vec.push(());
+        }
+
+        #[test]
+        fn test_try_reserve_capacity_overflow() {
+            let mut vec: MyVec<u64> = MyVec::new();
+            assert_eq!(
+                vec.try_reserve(isize::MAX as usize / 4),
+                Err(TryReserveError::CapacityOverflow)
+            );
+            vec.push(1);
+            assert_eq!(
+                vec.try_reserve_exact(usize::MAX),
+                Err(TryReserveError::CapacityOverflow)
+            );
+            assert_eq!(
+                MyVec::<u32>::try_with_capacity(usize::MAX / 2).err(),
+                Some(TryReserveError::CapacityOverflow)
+            );
+
+            // 失敗しても元の内容は変わらない
+            assert_eq!(vec.as_slice(), &[1]);
+            assert!(vec.try_reserve(10).is_ok());
+            assert!(vec.capacity() >= 11);
+        }
+
+        /// 確保の成否を切り替えられるテスト用アロケータ
+        #[derive(Default)]
+        struct FailingAllocator {
+            fail: Cell<bool>,
+        }
+
+        unsafe impl Allocator for FailingAllocator {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+                if self.fail.get() {
+                    Err(AllocError)
+                } else {
+                    Global.allocate(layout)
+                }
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        /// 確保・解放の回数と使用中のバイト数を数えるテスト用アロケータ
+        #[derive(Default)]
+        struct CountingAllocator {
+            allocations: Cell<usize>,
+            deallocations: Cell<usize>,
+            bytes_in_use: Cell<usize>,
+        }
+
+        unsafe impl Allocator for CountingAllocator {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+                self.allocations.set(self.allocations.get() + 1);
+                self.bytes_in_use
+                    .set(self.bytes_in_use.get() + layout.size());
+                Global.allocate(layout)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.deallocations.set(self.deallocations.get() + 1);
+                self.bytes_in_use
+                    .set(self.bytes_in_use.get() - layout.size());
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        /// 固定長のバッファから前から順に切り出すだけのバンプアリーナ
+        ///
+        /// 解放は何もしない（アリーナごと捨てる）。
+        struct BumpArena {
+            buf: UnsafeCell<[u8; 1024]>,
+            offset: Cell<usize>,
+        }
+
+        impl BumpArena {
+            fn new() -> Self {
+                BumpArena {
+                    buf: UnsafeCell::new([0; 1024]),
+                    offset: Cell::new(0),
+                }
+            }
+
+            fn contains(&self, p: *const u8) -> bool {
+                let start = self.buf.get() as *const u8;
+                let end = start.wrapping_add(1024);
+                start <= p && p < end
+            }
+        }
+
+        unsafe impl Allocator for BumpArena {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+                let base = self.buf.get() as *mut u8;
+                let offset = self.offset.get();
+                let padding = base.wrapping_add(offset).align_offset(layout.align());
+                let start = offset + padding;
+                let end = start.checked_add(layout.size()).ok_or(AllocError)?;
+                if end > 1024 {
+                    return Err(AllocError);
+                }
+                self.offset.set(end);
+                NonNull::new(unsafe { base.add(start) }).ok_or(AllocError)
+            }
+
+            unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {}
+        }
+
+        #[test]
+        fn test_try_reserve_alloc_error() {
+            let alloc = FailingAllocator::default();
+            let mut vec: MyVec<u64, _> = MyVec::new_in(&alloc);
+            vec.push(1);
+            vec.push(2);
+
+            alloc.fail.set(true);
+            assert_eq!(
+                vec.try_reserve(100),
+                Err(TryReserveError::AllocError {
+                    // len 2 + additional 100
+                    layout: Layout::array::<u64>(102).unwrap()
+                })
+            );
+            assert_eq!(
+                MyVec::<u64, _>::try_with_capacity_in(4, &alloc).err(),
+                Some(TryReserveError::AllocError {
+                    layout: Layout::array::<u64>(4).unwrap()
+                })
+            );
+
+            // 失敗しても元のバッファはそのまま使える
+            assert_eq!(vec.as_slice(), &[1, 2]);
+            assert_eq!(vec.capacity(), 2);
+
+            alloc.fail.set(false);
+            vec.push(3);
+            assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        }
+
+        #[test]
+        fn test_try_push() {
+            let alloc = FailingAllocator::default();
+            let count = Arc::new(AtomicUsize::new(0));
+            let mut vec = MyVec::with_capacity_in(2, &alloc);
+            for _ in 0..2 {
+                vec.push(DropCounter {
+                    count: count.clone(),
+                });
+            }
+
+            alloc.fail.set(true);
+            let result = vec.try_push(DropCounter {
+                count: count.clone(),
+            });
+
+            // 失敗した場合は値が返ってくる
+            let (value, e) = result.err().unwrap();
+            assert!(matches!(e, TryReserveError::AllocError { .. }));
+            assert_eq!(vec.len(), 2);
+            assert_eq!(count.load(Ordering::SeqCst), 0);
+
+            alloc.fail.set(false);
+            assert!(vec.try_push(value).is_ok());
+            assert_eq!(vec.len(), 3);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 3);
+        }
+
+        #[test]
+        fn test_zst_never_allocates() {
+            // ゼロサイズ型は確保しないので、アロケータが失敗しても影響を受けない
+            let alloc = FailingAllocator::default();
+            alloc.fail.set(true);
+
+            let mut vec = MyVec::new_in(&alloc);
+            assert!(vec.try_push(()).is_ok());
+            assert!(MyVec::<(), _>::try_with_capacity_in(100, &alloc).is_ok());
+        }
+
+        #[test]
+        fn test_counting_allocator() {
+            let alloc = CountingAllocator::default();
+
+            {
+                let mut vec = MyVec::new_in(&alloc);
+                for i in 0..100u32 {
+                    vec.push(i);
+                }
+                // 1 → 2 → 4 → ... → 128 と倍々で増える
+                assert_eq!(vec.capacity(), 128);
+                assert_eq!(alloc.bytes_in_use.get(), 128 * 4);
+
+                vec.shrink_to_fit();
+                assert_eq!(alloc.bytes_in_use.get(), 100 * 4);
+
+                // IntoIter が途中で捨てられてもバッファは解放される
+                let mut iter = vec.into_iter();
+                assert_eq!(iter.next(), Some(0));
+            }
+
+            assert_eq!(alloc.bytes_in_use.get(), 0);
+            assert_eq!(alloc.allocations.get(), alloc.deallocations.get());
+        }
+
+        #[test]
+        fn test_bump_arena() {
+            let arena = BumpArena::new();
+
+            let mut a = MyVec::new_in(&arena);
+            let mut b = MyVec::new_in(&arena);
+            for i in 0..10u64 {
+                a.push(i);
+                b.push(i * 10);
+            }
+
+            assert!(arena.contains(a.as_ptr() as *const u8));
+            assert!(arena.contains(b.as_ptr() as *const u8));
+            assert_eq!(a.as_slice(), &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+            assert_eq!(b[9], 90);
+
+            // アリーナを使い切ると確保に失敗する
+            assert!(matches!(
+                a.try_reserve_exact(1000),
+                Err(TryReserveError::AllocError { .. })
+            ));
+        }
+
+        #[test]
+        fn test_try_reserve_error_display() {
+            assert_eq!(
+                TryReserveError::CapacityOverflow.to_string(),
+                "capacity overflow"
+            );
+            let e = TryReserveError::AllocError {
+                layout: Layout::array::<u32>(4).unwrap(),
+            };
+            assert_eq!(e.to_string(), "memory allocation of 16 bytes failed");
+        }
+
+        #[test]
+        fn test_clone() {
+            let vec: MyVec<String> = MyVec::from(["a".to_string(), "b".to_string()]);
+            let mut cloned = vec.clone();
+            cloned.push("c".to_string());
+
+            assert_eq!(vec, ["a", "b"]);
+            assert_eq!(cloned, ["a", "b", "c"]);
+        }
+
+        #[test]
+        fn test_clone_panic_safety() {
+            use std::panic::{catch_unwind, AssertUnwindSafe};
+
+            // 3 回目の clone でパニックする型
+            struct PanicOnClone {
+                count: Arc<AtomicUsize>,
+                clones: Arc<AtomicUsize>,
+            }
+
+            impl Clone for PanicOnClone {
+                fn clone(&self) -> Self {
+                    if self.clones.fetch_add(1, Ordering::SeqCst) == 2 {
+                        panic!("clone panic");
+                    }
+                    PanicOnClone {
+                        count: self.count.clone(),
+                        clones: self.clones.clone(),
+                    }
+                }
+            }
+
+            impl Drop for PanicOnClone {
+                fn drop(&mut self) {
+                    self.count.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
+            let count = Arc::new(AtomicUsize::new(0));
+            let clones = Arc::new(AtomicUsize::new(0));
+            let mut vec = MyVec::new();
+            for _ in 0..5 {
+                vec.push(PanicOnClone {
+                    count: count.clone(),
+                    clones: clones.clone(),
+                });
+            }
+
+            let result = catch_unwind(AssertUnwindSafe(|| vec.clone()));
+            assert!(result.is_err());
+            // clone 済みの 2 つだけが drop される
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 7);
+        }
+
+        #[test]
+        fn test_debug() {
+            let vec = MyVec::from([1, 2, 3]);
+            assert_eq!(format!("{:?}", vec), "[1, 2, 3]");
+
+            let empty: MyVec<i32> = MyVec::default();
+            assert_eq!(format!("{:?}", empty), "[]");
+        }
+
+        #[test]
+        fn test_eq_and_ord() {
+            let a = MyVec::from([1, 2, 3]);
+            let b: MyVec<i32> = (1..=3).collect();
+            let c = MyVec::from([1, 2, 4]);
+
+            assert_eq!(a, b);
+            assert_ne!(a, c);
+            assert_eq!(a, vec![1, 2, 3]);
+            assert_eq!(a, &[1, 2, 3][..]);
+            assert!(a < c);
+            let shorter = MyVec::from([1, 2]);
+            assert!(shorter < a);
+            assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
+        }
+
+        #[test]
+        fn test_hash_matches_slice() {
+            use std::collections::hash_map::DefaultHasher;
+
+            fn hash_of<H: Hash + ?Sized>(value: &H) -> u64 {
+                let mut hasher = DefaultHasher::new();
+                value.hash(&mut hasher);
+                hasher.finish()
+            }
+
+            let vec = MyVec::from([1, 2, 3]);
+            assert_eq!(hash_of(&vec), hash_of(&[1, 2, 3][..]));
+            assert_eq!(hash_of(&vec), hash_of(&vec![1, 2, 3]));
+        }
+
+        #[test]
+        fn test_as_hash_map_key() {
+            use crate::week1_basic_structures::hash_map::SimpleHashMap;
+
+            let mut map = SimpleHashMap::new();
+            map.insert(MyVec::from([1, 2]), "one-two");
+            map.insert(MyVec::from([3]), "three");
+            map.insert(MyVec::from([1, 2]), "updated");
+
+            assert_eq!(map.len(), 2);
+            assert_eq!(map.get(&MyVec::from([1, 2])), Some(&"updated"));
+            assert_eq!(map.get(&MyVec::from([3])), Some(&"three"));
+            assert_eq!(map.get(&MyVec::new()), None);
+        }
+
+        #[test]
+        fn test_from_iter_and_extend() {
+            let mut vec: MyVec<i32> = (0..3).collect();
+            vec.extend(vec![3, 4]);
+            vec.extend(&[5, 6]);
+            vec.extend([7].iter());
+
+            assert_eq!(vec, [0, 1, 2, 3, 4, 5, 6, 7]);
+        }
+
+        #[test]
+        fn test_from_vec() {
+            let source = vec!["a".to_string(), "b".to_string()];
+            let ptr = source.as_ptr();
+
+            let mut vec = MyVec::from(source);
+            // バッファがそのまま引き継がれる
+            assert_eq!(vec.as_ptr(), ptr);
+            assert_eq!(vec, ["a", "b"]);
+
+            vec.push("c".to_string());
+            assert_eq!(vec, ["a", "b", "c"]);
+
+            let empty: MyVec<i32> = MyVec::from(Vec::new());
+            assert!(empty.is_empty());
+            assert_eq!(empty.capacity(), 0);
+
+            let zst: MyVec<()> = MyVec::from(vec![(); 3]);
+            assert_eq!(zst.len(), 3);
+            assert_eq!(zst.capacity(), usize::MAX);
+        }
+
+        #[test]
+        fn test_from_array_drop_count() {
+            let count = Arc::new(AtomicUsize::new(0));
+            let arr = [
+                DropCounter {
+                    count: count.clone(),
+                },
+                DropCounter {
+                    count: count.clone(),
+                },
+            ];
+
+            let vec = MyVec::from(arr);
+            assert_eq!(count.load(Ordering::SeqCst), 0);
+            drop(vec);
+            assert_eq!(count.load(Ordering::SeqCst), 2);
+        }
+
+        /// 型が Send / Sync かどうかをコンパイル時に判定するための補助型
+        ///
+        /// 境界を満たす場合は inherent impl の定数が、満たさない場合は
+        /// トレイトのデフォルト定数が選ばれることを利用している。
+        struct Probe<T>(PhantomData<T>);
+
+        trait NotSendOrSync {
+            const IS_SEND: bool = false;
+            const IS_SYNC: bool = false;
+        }
+
+        impl<T> NotSendOrSync for Probe<T> {}
+
+        impl<T: Send> Probe<T> {
+            const IS_SEND: bool = true;
+        }
+
+        impl<T: Sync> Probe<T> {
+            const IS_SYNC: bool = true;
+        }
+
+        // Send / Sync の条件をコンパイル時に検証する（条件が崩れるとテストのビルドが失敗する）
+        const _: () = {
+            use std::cell::Cell;
+            use std::rc::Rc;
+
+            assert!(Probe::<MyVec<i32>>::IS_SEND);
+            assert!(Probe::<MyVec<String>>::IS_SEND);
+            assert!(Probe::<IntoIter<String>>::IS_SEND);
+            assert!(Probe::<Iter<'static, i32>>::IS_SEND);
+            assert!(Probe::<IterMut<'static, i32>>::IS_SEND);
+
+            // Send でない要素を持つとスレッドをまたげない
+            assert!(!Probe::<MyVec<Rc<i32>>>::IS_SEND);
+            assert!(!Probe::<IntoIter<Rc<i32>>>::IS_SEND);
+            // Cell は Send だが Sync ではないので、&Cell を返す Iter は Send にならない
+            assert!(Probe::<MyVec<Cell<i32>>>::IS_SEND);
+            assert!(!Probe::<Iter<'static, Cell<i32>>>::IS_SEND);
+
+            assert!(Probe::<MyVec<i32>>::IS_SYNC);
+            assert!(Probe::<Iter<'static, i32>>::IS_SYNC);
+            assert!(!Probe::<MyVec<Cell<i32>>>::IS_SYNC);
+            assert!(!Probe::<MyVec<Rc<i32>>>::IS_SYNC);
+        };
+
+        #[test]
+        fn test_move_to_thread() {
+            let vec: MyVec<String> = (0..100).map(|i| i.to_string()).collect();
+
+            let handle = std::thread::spawn(move || {
+                let mut vec = vec;
+                vec.push("done".to_string());
+                vec
+            });
+            let vec = handle.join().unwrap();
+            assert_eq!(vec.len(), 101);
+            assert_eq!(vec[100], "done");
+        }
+
+        #[test]
+        fn test_share_between_threads() {
+            let vec: MyVec<u64> = (1..=100).collect();
+
+            // Sync なので &MyVec を複数スレッドから読める
+            let sums: Vec<u64> = std::thread::scope(|s| {
+                let handles: Vec<_> = vec
+                    .chunks(25)
+                    .map(|chunk| s.spawn(move || chunk.iter().sum::<u64>()))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            assert_eq!(sums.iter().sum::<u64>(), 5050);
+            assert_eq!(vec.iter().sum::<u64>(), 5050);
+        }
+
+        #[test]
+        fn test_covariance() {
+            // MyVec<T> は T について共変なので、より長い寿命の参照を持つ MyVec を
+            // 短い寿命の MyVec として扱える
+            fn shorten<'a>(vec: MyVec<&'static str>) -> MyVec<&'a str> {
+                vec
+            }
+
+            let owned = String::from("local");
+            let mut vec = shorten(MyVec::from(["static"]));
+            vec.push(&owned);
+            assert_eq!(vec, ["static", "local"]);
+        }
+
+        #[test]
+        fn test_growth_policy_capacities() {
+            fn capacities(policy: GrowthPolicy, pushes: usize) -> Vec<usize> {
+                let mut vec = MyVec::with_growth_policy(policy);
+                let mut seen = Vec::new();
+                for i in 0..pushes {
+                    vec.push(i as u32);
+                    if seen.last() != Some(&vec.capacity()) {
+                        seen.push(vec.capacity());
+                    }
+                }
+                seen
+            }
+
+            assert_eq!(
+                capacities(GrowthPolicy::Doubling, 20),
+                vec![1, 2, 4, 8, 16, 32]
+            );
+            assert_eq!(
+                capacities(GrowthPolicy::OneAndHalf, 20),
+                vec![1, 2, 3, 4, 6, 9, 13, 19, 28]
+            );
+            assert_eq!(
+                capacities(GrowthPolicy::FixedIncrement(5), 20),
+                vec![5, 10, 15, 20]
+            );
+            // u32 は 4 バイトなので、2 のべき乗バイトに切り上げると容量も 2 のべき乗になる
+            assert_eq!(
+                capacities(GrowthPolicy::SizeClass, 20),
+                vec![1, 2, 4, 8, 16, 32]
+            );
+        }
+
+        #[test]
+        fn test_size_class_rounding() {
+            // 12 バイトの要素: 2 倍の容量ぶんのバイト数を 2 のべき乗へ切り上げる
+            let policy = GrowthPolicy::SizeClass;
+            assert_eq!(policy.next_capacity(0, 1, 12), 1); // 12 → 16 バイト
+            assert_eq!(policy.next_capacity(1, 2, 12), 2); // 24 → 32 バイト
+            assert_eq!(policy.next_capacity(2, 3, 12), 5); // 48 → 64 バイト
+            assert_eq!(policy.next_capacity(5, 6, 12), 10); // 120 → 128 バイト
+
+            // どの方針でも required 以上になる
+            assert_eq!(GrowthPolicy::Doubling.next_capacity(4, 100, 8), 100);
+            assert_eq!(GrowthPolicy::FixedIncrement(0).next_capacity(4, 5, 8), 5);
+            assert_eq!(GrowthPolicy::SizeClass.next_capacity(0, 3, 0), 3);
+        }
+
+        #[test]
+        fn test_growth_policy_keeps_elements() {
+            let mut vec = MyVec::with_growth_policy(GrowthPolicy::OneAndHalf);
+            for i in 0..100 {
+                vec.push(format!("{}", i));
+            }
+            let cloned = vec.clone();
+
+            assert_eq!(vec.growth_policy(), GrowthPolicy::OneAndHalf);
+            assert_eq!(cloned.growth_policy(), GrowthPolicy::OneAndHalf);
+            assert_eq!(vec[99], "99");
+            assert_eq!(cloned, vec);
+        }
+
+        #[test]
+        fn test_policy_and_stats_do_not_grow_my_vec() {
+            // デフォルトの拡張方針はサイズ 0、統計はアロケータ側にあるので、
+            // MyVec 本体は ptr / len / capacity だけ
+            assert_eq!(mem::size_of::<MyVec<u64>>(), 3 * mem::size_of::<usize>());
+            assert_eq!(
+                mem::size_of::<MyVec<u64, &StatsAllocator>>(),
+                4 * mem::size_of::<usize>()
+            );
+        }
+
+        #[test]
+        fn test_stats_amortized_push() {
+            let n = 100_000;
+            let stats = StatsAllocator::new();
+            let mut vec = MyVec::new_in(&stats);
+            for i in 0..n {
+                vec.push(i as u64);
+            }
+
+            let snapshot = stats.stats();
+            // 1, 2, 4, ..., 131072 と 18 回確保する
+            assert_eq!(snapshot.reallocations, 18);
+            assert_eq!(snapshot.largest_allocation, 131_072 * 8);
+            // 倍々に増やすと、コピー量の合計は 1 + 2 + ... + 65536 < 2n 要素ぶん（1 要素あたり償却 O(1)）
+            assert!(snapshot.bytes_copied < 2 * n * 8);
+
+            vec.truncate(10);
+            vec.shrink_to_fit();
+            let snapshot = stats.stats();
+            assert_eq!(snapshot.reallocations, 19);
+            assert_eq!(snapshot.largest_allocation, 131_072 * 8);
+
+            stats.reset();
+            assert_eq!(stats.stats(), AllocStats::default());
+        }
+
+        #[test]
+        fn test_stats_fixed_increment_is_linear() {
+            let n = 1_000;
+            let stats = StatsAllocator::new();
+            let mut vec = MyVec::with_growth_in(GrowthPolicy::FixedIncrement(10), &stats);
+            for i in 0..n {
+                vec.push(i as u64);
+            }
+
+            // 一定数ずつ増やすと再確保の回数が n に比例する
+            assert_eq!(vec.growth_policy(), GrowthPolicy::FixedIncrement(10));
+            assert_eq!(stats.stats().reallocations, n / 10);
+        }
+
+        #[test]
+        fn test_stats_counts_copies_when_moved() {
+            // 毎回新しい場所に確保し直すバンプアリーナでは、伸長のたびに全要素がコピーされる
+            let arena = BumpArena::new();
+            let stats = StatsAllocator::new_in(&arena);
+            let mut vec = MyVec::new_in(&stats);
+            for i in 0..8u32 {
+                vec.push(i);
+            }
+
+            // 容量 1 → 2 → 4 → 8。コピーは 1 + 2 + 4 要素
+            let snapshot = stats.stats();
+            assert_eq!(snapshot.reallocations, 4);
+            assert_eq!(snapshot.bytes_copied, (1 + 2 + 4) * 4);
+            assert_eq!(snapshot.largest_allocation, 8 * 4);
+        }
+
+        #[test]
+        fn test_stats_shared_between_vecs() {
+            // 1 つの StatsAllocator を共有すると、統計は両方の MyVec を合わせたものになる
+            let stats = StatsAllocator::new();
+            let mut small: MyVec<u8, _> = MyVec::new_in(&stats);
+            let mut large: MyVec<u64, _> = MyVec::new_in(&stats);
+            for i in 0..100u8 {
+                small.push(i);
+            }
+            for i in 0..20u64 {
+                large.push(i);
+            }
+
+            let snapshot = stats.stats();
+            // small: 1, 2, ..., 128 の 8 回、large: 1, 2, ..., 32 の 6 回
+            assert_eq!(snapshot.reallocations, 8 + 6);
+            // 最大のバッファは large の 32 × 8 バイトで、small の容量（128 バイト）とは関係ない
+            assert_eq!(snapshot.largest_allocation, 32 * 8);
+            assert_eq!(small.capacity(), 128);
+        }
+
+        #[test]
+        fn test_week3_sorts_on_my_vec() {
+            use crate::week3_search_sort::advanced_sorts::{merge_sort, quick_sort};
+            use crate::week3_search_sort::search::binary_search;
+
+            let mut vec = MyVec::new();
+            for x in [3, 1, 4, 1, 5, 9, 2, 6, 5] {
+                vec.push(x);
+            }
+
+            quick_sort(&mut vec);
+            assert_eq!(vec.as_slice(), &[1, 1, 2, 3, 4, 5, 5, 6, 9]);
+            assert_eq!(binary_search(&vec, &9), Some(8));
+
+            vec.reverse();
+            merge_sort(&mut vec);
+            assert_eq!(vec.as_slice(), &[1, 1, 2, 3, 4, 5, 5, 6, 9]);
+        }
+    }
+}
+
+// ---------------------------------------------------------
+// 課題2: 単方向連結リスト
+// ---------------------------------------------------------
+
+pub mod linked_list {
+    use std::cmp::Ordering;
+    use std::fmt;
+    use std::iter::FusedIterator;
+    use std::marker::PhantomData;
+    use std::mem;
+    use std::ptr::NonNull;
+
+    type Link<T> = Option<NonNull<Node<T>>>;
+
+    /// ノード構造
+    struct Node<T> {
+        data: T,
+        next: Link<T>,
+    }
+
+    /// 単方向連結リスト
+    ///
+    /// 各ノードは Box で確保したものを生ポインタとして保持し、リスト自身が所有する
+    /// （`DoublyLinkedList` と同じ方式）。head と tail が同じノードを指しても、
+    /// どちらも生ポインタなので一方を使うともう一方が無効になる、ということが起きない。
+    ///
+    /// 不変条件: リストが空なら head / tail は None、空でなければ tail は最後のノードを指す。
+    pub struct LinkedList<T> {
+        head: Link<T>,
+        tail: Link<T>,
+        len: usize,
+        // Box<Node<T>> を所有していることをコンパイラに伝える（drop check のため）
+        _marker: PhantomData<Box<Node<T>>>,
+    }
+
+    // ノードはすべてリストが所有しているので、Box<Node<T>> と同じ条件で送受信できる
+    unsafe impl<T: Send> Send for LinkedList<T> {}
+    unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
+    impl<T> LinkedList<T> {
+        /// 新しい空のリストを作成
+        pub fn new() -> Self {
+            LinkedList {
+                head: None,
+                tail: None,
+                len: 0,
+                _marker: PhantomData,
+            }
+        }
+
+        /// 先頭に要素を追加
+        pub fn push_front(&mut self, data: T) {
+            // 1. 新しいノードを作成
+            // 2. 新しいノードの next に現在の head を設定
+            let node = NonNull::from(Box::leak(Box::new(Node {
+                data,
+                next: self.head,
+            })));
+            // 3. 空のリストだった場合、新しいノードが末尾でもある
+            if self.tail.is_none() {
+                self.tail = Some(node);
+            }
+            // 4. head を新しいノードに更新
+            self.head = Some(node);
+            // 5. len をインクリメント
+            self.len += 1;
+        }
+
+        /// 先頭の要素を削除して返す
+        pub fn pop_front(&mut self) -> Option<T> {
+            self.pop_node_front().map(|node| node.data)
+        }
+
+        /// 末尾に要素を追加（O(1)）
+        ///
+        /// 末尾まで辿らずに、tail ポインタの指すノードの後ろへ直接つなぐ。
+        pub fn push_back(&mut self, data: T) {
+            self.push_node_back(Box::new(Node { data, next: None }));
+        }
+
+        /// 先頭の要素を参照
+        pub fn peek(&self) -> Option<&T> {
+            // SAFETY: head は self が所有するノードを指し、&self の間は変更されない
+            self.head.map(|node| unsafe { &(*node.as_ptr()).data })
+        }
+
+        /// 末尾の要素を参照（O(1)）
+        pub fn peek_back(&self) -> Option<&T> {
+            // SAFETY: peek と同じ
+            self.tail.map(|node| unsafe { &(*node.as_ptr()).data })
+        }
+
+        /// 要素数
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.head.is_none()
+        }
+
+        /// 先頭から順に要素への参照を返すイテレータ
+        pub fn iter(&self) -> Iter<'_, T> {
+            Iter {
+                current: self.head,
+                remaining: self.len,
+                _marker: PhantomData,
+            }
+        }
+
+        /// 先頭から順に要素への可変参照を返すイテレータ
+        pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+            IterMut {
+                current: self.head,
+                remaining: self.len,
+                _marker: PhantomData,
+            }
+        }
+
+        /// リストを逆順にする（O(n)）
+        ///
+        /// 要素を移動せず、各ノードの next を付け替えるだけで反転する。
+        pub fn reverse(&mut self) {
+            // 元の先頭が新しい末尾になる
+            self.tail = self.head;
+
+            let mut reversed: Link<T> = None;
+            let mut current = self.head;
+            while let Some(node) = current {
+                // SAFETY: node は self が所有するノードで、他に参照は存在しない
+                unsafe {
+                    current = (*node.as_ptr()).next;
+                    (*node.as_ptr()).next = reversed;
+                }
+                reversed = Some(node);
+            }
+            self.head = reversed;
+        }
+
+        /// at 番目以降の要素を切り離し、新しいリストとして返す（O(at)）
+        ///
+        /// # Panics
+        /// at > len の場合
+        pub fn split_off(&mut self, at: usize) -> LinkedList<T> {
+            assert!(
+                at <= self.len,
+                "split_off index (is {}) should be <= len (is {})",
+                at,
+                self.len
+            );
+            if at == 0 {
+                return mem::take(self);
+            }
+
+            // at - 1 番目のノード（切り離した後の新しい末尾）まで辿る
+            let mut node = self.head.expect("list is not empty");
+            // SAFETY: at <= len なので、辿るノードはすべて self が所有している
+            let rest = unsafe {
+                for _ in 1..at {
+                    node = (*node.as_ptr()).next.expect("at < len");
+                }
+                (*node.as_ptr()).next.take()
+            };
+
+            let split = LinkedList {
+                head: rest,
+                tail: if rest.is_some() { self.tail } else { None },
+                len: self.len - at,
+                _marker: PhantomData,
+            };
+            self.tail = Some(node);
+            self.len = at;
+            split
+        }
+
+        /// other の要素をすべて末尾に連結し、other を空にする（O(1)）
+        pub fn append(&mut self, other: &mut LinkedList<T>) {
+            let Some(other_head) = other.head.take() else {
+                return;
+            };
+            match self.tail {
+                // SAFETY: 不変条件より、空でないリストの tail は self が所有する最後のノードを指す
+                Some(tail) => unsafe { (*tail.as_ptr()).next = Some(other_head) },
+                None => self.head = Some(other_head),
+            }
+            self.tail = other.tail.take();
+            self.len += mem::replace(&mut other.len, 0);
+        }
+
+        /// 条件を満たす要素をすべて削除し、削除した個数を返す（O(n)）
+        ///
+        /// 残す要素の順序は変わらない。
+        pub fn remove_if<F>(&mut self, mut pred: F) -> usize
+        where
+            F: FnMut(&T) -> bool,
+        {
+            // 途中で pred が panic しても、未処理のノードは rest の Drop で、
+            // 取り出し中のノードは Box の Drop で解放される
+            let mut rest = mem::take(self);
+            let mut removed = 0;
+            while let Some(node) = rest.pop_node_front() {
+                if pred(&node.data) {
+                    removed += 1;
+                } else {
+                    self.push_node_back(node);
+                }
+            }
+            removed
+        }
+
+        /// 要素を昇順に並べ替える（安定ソート）
+        ///
+        /// # Examples
+        /// ```
+        /// let mut list: LinkedList<i32> = vec![3, 1, 2].into_iter().collect();
+        /// list.sort();
+        /// assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        /// ```
+        pub fn sort(&mut self)
+        where
+            T: Ord,
+        {
+            self.sort_by(T::cmp);
+        }
+
+        /// 比較関数に従って要素を並べ替える（安定ソート）
+        ///
+        /// # 計算量
+        /// - 時間: O(n log n)
+        /// - 空間: O(log n)（再帰スタック）。ヒープ確保は行わない
+        /// - 安定: Yes
+        ///
+        /// # アルゴリズム
+        /// `advanced_sorts::merge_sort` と同じ分割統治法だが、要素をコピーせず
+        /// ノードの付け替えだけでマージするので `T: Clone` を必要としない。
+        ///
+        /// 比較関数が panic した場合、まだマージされていない要素は drop される。
+        pub fn sort_by<F>(&mut self, mut compare: F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            Self::merge_sort_by(self, &mut compare);
+        }
+
+        fn merge_sort_by<F>(list: &mut LinkedList<T>, compare: &mut F)
+        where
+            F: FnMut(&T, &T) -> Ordering,
+        {
+            // 1. 1 要素以下なら終了
+            if list.len <= 1 {
+                return;
+            }
+            // 2. 半分に分割
+            let mut right = list.split_off(list.len / 2);
+            // 3. 左右をそれぞれ再帰的にソート
+            Self::merge_sort_by(list, compare);
+            Self::merge_sort_by(&mut right, compare);
+            // 4. 先頭同士を比べながら、小さい方のノードを付け替えていく
+            let mut left = mem::take(list);
+            while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+                // 等しいときは左を先に取るので安定になる
+                let source = if compare(r, l) == Ordering::Less {
+                    &mut right
+                } else {
+                    &mut left
+                };
+                let node = source.pop_node_front().expect("head is Some");
+                list.push_node_back(node);
+            }
+            // 5. 残りをまとめて連結
+            list.append(&mut left);
+            list.append(&mut right);
+        }
+
+        /// 既存のノードを末尾につなぐ（O(1)）
+        ///
+        /// Box の所有権はリストに移り、以降は生ポインタとしてだけ扱う。
+        fn push_node_back(&mut self, mut node: Box<Node<T>>) {
+            node.next = None;
+            let node = NonNull::from(Box::leak(node));
+
+            match self.tail {
+                // SAFETY: 不変条件より、空でないリストの tail は self が所有する最後のノードを指す
+                Some(tail) => unsafe { (*tail.as_ptr()).next = Some(node) },
+                None => self.head = Some(node),
+            }
+            self.tail = Some(node);
+            self.len += 1;
+        }
+
+        /// 先頭のノードを切り離し、Box に戻して返す（O(1)）
+        fn pop_node_front(&mut self) -> Option<Box<Node<T>>> {
+            self.head.map(|node| {
+                // SAFETY: head は push_front / push_node_back で Box から作ったノードで、
+                // ここでリストから外すので、Box に戻しても他から参照されない
+                let node = unsafe { Box::from_raw(node.as_ptr()) };
+                self.head = node.next;
+                if self.head.is_none() {
+                    self.tail = None;
+                }
+                self.len -= 1;
+                node
+            })
+        }
+    }
+
+    impl<T> Default for LinkedList<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// 要素への参照を返すイテレータ（`LinkedList::iter` で作る）
+    pub struct Iter<'a, T> {
+        current: Link<T>,
+        remaining: usize,
+        _marker: PhantomData<&'a Node<T>>,
+    }
+
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.current.map(|node| {
+                // SAFETY: リストを &'a で借用している間、ノードは解放も変更もされない
+                unsafe {
+                    self.current = (*node.as_ptr()).next;
+                    self.remaining -= 1;
+                    &(*node.as_ptr()).data
+                }
+            })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.remaining, Some(self.remaining))
+        }
+    }
+
+    impl<T> ExactSizeIterator for Iter<'_, T> {}
+    impl<T> FusedIterator for Iter<'_, T> {}
+
+    impl<T> Clone for Iter<'_, T> {
+        fn clone(&self) -> Self {
+            Iter {
+                current: self.current,
+                remaining: self.remaining,
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    // Iter は &'a Node<T> と同じ扱い
+    unsafe impl<T: Sync> Send for Iter<'_, T> {}
+    unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+    /// 要素への可変参照を返すイテレータ（`LinkedList::iter_mut` で作る）
+    pub struct IterMut<'a, T> {
+        current: Link<T>,
+        remaining: usize,
+        _marker: PhantomData<&'a mut Node<T>>,
+    }
+
+    impl<'a, T> Iterator for IterMut<'a, T> {
+        type Item = &'a mut T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.current.map(|node| {
+                // SAFETY: リストを &'a mut で借用しており、各ノードの data への &mut は 1 度しか返さない。
+                // ノード全体ではなく data だけを借用するので、next の読み出しと重ならない
+                unsafe {
+                    self.current = (*node.as_ptr()).next;
+                    self.remaining -= 1;
+                    &mut (*node.as_ptr()).data
+                }
+            })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.remaining, Some(self.remaining))
+        }
+    }
+
+    impl<T> ExactSizeIterator for IterMut<'_, T> {}
+    impl<T> FusedIterator for IterMut<'_, T> {}
+
+    // IterMut は &'a mut Node<T> と同じ扱い
+    unsafe impl<T: Send> Send for IterMut<'_, T> {}
+    unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+    /// 要素を先頭から所有権ごと取り出すイテレータ（`into_iter` で作る）
+    pub struct IntoIter<T> {
+        list: LinkedList<T>,
+    }
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            self.list.pop_front()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.list.len, Some(self.list.len))
+        }
+    }
+
+    impl<T> ExactSizeIterator for IntoIter<T> {}
+    impl<T> FusedIterator for IntoIter<T> {}
+
+    impl<T> IntoIterator for LinkedList<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+
+        fn into_iter(self) -> IntoIter<T> {
+            IntoIter { list: self }
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a LinkedList<T> {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+
+        fn into_iter(self) -> Iter<'a, T> {
+            self.iter()
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+        type Item = &'a mut T;
+        type IntoIter = IterMut<'a, T>;
+
+        fn into_iter(self) -> IterMut<'a, T> {
+            self.iter_mut()
+        }
+    }
+
+    impl<T> FromIterator<T> for LinkedList<T> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            let mut list = LinkedList::new();
+            list.extend(iter);
+            list
+        }
+    }
+
+    impl<T> Extend<T> for LinkedList<T> {
+        /// 末尾に順に追加する（push_back が O(1) なので全体で O(n)）
+        fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+            for item in iter {
+                self.push_back(item);
+            }
+        }
+    }
+
+    impl<'a, T: Copy + 'a> Extend<&'a T> for LinkedList<T> {
+        fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+            self.extend(iter.into_iter().copied());
+        }
+    }
+
+    impl<T: Clone> Clone for LinkedList<T> {
+        fn clone(&self) -> Self {
+            self.iter().cloned().collect()
+        }
+    }
+
+    impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    impl<T: PartialEq> PartialEq for LinkedList<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.len == other.len && self.iter().eq(other.iter())
+        }
+    }
+
+    impl<T: Eq> Eq for LinkedList<T> {}
+
+    impl<T> Drop for LinkedList<T> {
+        /// ノードを先頭から 1 つずつ切り離しながら解放する
+        ///
+        /// ノードは next を生ポインタで持つので、1 つのノードの drop が
+        /// 後ろのノードの drop を呼ぶ再帰にはならない（長いリストでもスタックが溢れない）。
+        fn drop(&mut self) {
+            while self.pop_node_front().is_some() {}
+        }
+    }
+
+    impl<T: fmt::Display> fmt::Display for LinkedList<T> {
+        /// `1 -> 2 -> 3 -> None` の形式で書き出す
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for item in self.iter() {
+                write!(f, "{} -> ", item)?;
+            }
+            write!(f, "None")
+        }
+    }
+
+    // LinkedList と並べて linked_list::ArenaList などとして使えるようにする
+    // （バイナリクレートなので、テスト以外から使われていなくても警告しない）
+    #[allow(unused_imports)]
+    pub use arena::{ArenaList, Handle, Remap};
+    #[allow(unused_imports)]
+    pub use persistent::{rc::PersistentList, sync::PersistentList as ArcPersistentList};
+    #[allow(unused_imports)]
+    pub use skip_list::{SkipList, SplitMix64};
+
+    /// 永続リスト（不変で、末尾を複数のリストで共有する連結リスト）
+    ///
+    /// `prepend` や `tail` は元のリストを変更せず、ノードを共有した新しいリストを O(1) で返す。
+    /// 参照カウントに `Rc` を使う `rc::PersistentList` と、スレッド間で共有できる
+    /// `Arc` 版の `sync::PersistentList` がある（実装はマクロで共通化している）。
+    pub mod persistent {
+        macro_rules! persistent_list {
+            ($ptr:ident) => {
+                use std::fmt;
+                use std::iter::FusedIterator;
+
+                type Link<T> = Option<$ptr<Node<T>>>;
+
+                struct Node<T> {
+                    elem: T,
+                    next: Link<T>,
+                }
+
+                /// 不変の単方向リスト（末尾を他のリストと共有できる）
+                ///
+                /// clone は先頭ノードの参照カウントを増やすだけなので O(1)。
+                pub struct PersistentList<T> {
+                    head: Link<T>,
+                    len: usize,
+                }
+
+                impl<T> PersistentList<T> {
+                    /// 空のリストを作成
+                    pub fn new() -> Self {
+                        PersistentList { head: None, len: 0 }
+                    }
+
+                    /// 先頭に要素を加えた新しいリストを返す（O(1)、self はそのまま）
+                    pub fn prepend(&self, elem: T) -> Self {
+                        PersistentList {
+                            head: Some($ptr::new(Node {
+                                elem,
+                                next: self.head.clone(),
+                            })),
+                            len: self.len + 1,
+                        }
+                    }
+
+                    /// 先頭を除いた残りのリストを返す（O(1)、空なら空のリスト）
+                    pub fn tail(&self) -> Self {
+                        PersistentList {
+                            head: self.head.as_ref().and_then(|node| node.next.clone()),
+                            len: self.len.saturating_sub(1),
+                        }
+                    }
+
+                    /// 先頭の要素を参照
+                    pub fn head(&self) -> Option<&T> {
+                        self.head.as_deref().map(|node| &node.elem)
+                    }
+
+                    /// 要素数
+                    pub fn len(&self) -> usize {
+                        self.len
+                    }
+
+                    /// 空かどうか
+                    pub fn is_empty(&self) -> bool {
+                        self.head.is_none()
+                    }
+
+                    /// 2 つのリストが同じノードから始まっている（完全に共有している）か
+                    pub fn ptr_eq(&self, other: &Self) -> bool {
+                        match (&self.head, &other.head) {
+                            (Some(a), Some(b)) => $ptr::ptr_eq(a, b),
+                            (None, None) => true,
+                            _ => false,
+                        }
+                    }
+
+                    /// 先頭から順に要素への参照を返すイテレータ
+                    pub fn iter(&self) -> Iter<'_, T> {
+                        Iter {
+                            next: self.head.as_deref(),
+                            remaining: self.len,
+                        }
+                    }
+                }
+
+                impl<T> Drop for PersistentList<T> {
+                    /// 他のリストと共有していないノードだけを、ループで解放する
+                    ///
+                    /// 最後の参照だったノードは into_inner で取り出して next を切り離す。
+                    /// 共有されているノードに着いたら、そこから先は他のリストのものなので止まる。
+                    ///
+                    /// try_unwrap だと、2 つのスレッドが同じノードを同時に捨てたときに
+                    /// 両方とも失敗し、最後に残った参照の drop が再帰で鎖全体を解放してしまう。
+                    /// into_inner は「最後の参照なら中身を返す」を不可分に行うので、必ずどちらかが取り出す。
+                    fn drop(&mut self) {
+                        let mut head = self.head.take();
+                        while let Some(node) = head {
+                            match $ptr::into_inner(node) {
+                                Some(mut node) => head = node.next.take(),
+                                None => break,
+                            }
+                        }
+                    }
+                }
+
+                impl<T> Clone for PersistentList<T> {
+                    fn clone(&self) -> Self {
+                        PersistentList {
+                            head: self.head.clone(),
+                            len: self.len,
+                        }
+                    }
+                }
+
+                impl<T> Default for PersistentList<T> {
+                    fn default() -> Self {
+                        Self::new()
+                    }
+                }
+
+                impl<T> FromIterator<T> for PersistentList<T> {
+                    /// イテレータの順序のままリストにする
+                    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+                        // 先頭に積んでいくので、逆順にしてから prepend する
+                        let items: Vec<T> = iter.into_iter().collect();
+                        let mut list = PersistentList::new();
+                        for item in items.into_iter().rev() {
+                            list = list.prepend(item);
+                        }
+                        list
+                    }
+                }
+
+                impl<T: fmt::Debug> fmt::Debug for PersistentList<T> {
+                    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        f.debug_list().entries(self.iter()).finish()
+                    }
+                }
+
+                impl<T: PartialEq> PartialEq for PersistentList<T> {
+                    fn eq(&self, other: &Self) -> bool {
+                        self.len == other.len && self.iter().eq(other.iter())
+                    }
+                }
+
+                impl<T: Eq> Eq for PersistentList<T> {}
+
+                /// 要素への参照を返すイテレータ（`PersistentList::iter` で作る）
+                pub struct Iter<'a, T> {
+                    next: Option<&'a Node<T>>,
+                    remaining: usize,
+                }
+
+                impl<'a, T> Iterator for Iter<'a, T> {
+                    type Item = &'a T;
+
+                    fn next(&mut self) -> Option<&'a T> {
+                        self.next.map(|node| {
+                            self.next = node.next.as_deref();
+                            self.remaining -= 1;
+                            &node.elem
+                        })
+                    }
+
+                    fn size_hint(&self) -> (usize, Option<usize>) {
+                        (self.remaining, Some(self.remaining))
+                    }
+                }
+
+                impl<T> ExactSizeIterator for Iter<'_, T> {}
+                impl<T> FusedIterator for Iter<'_, T> {}
+
+                impl<T> Clone for Iter<'_, T> {
+                    fn clone(&self) -> Self {
+                        Iter {
+                            next: self.next,
+                            remaining: self.remaining,
+                        }
+                    }
+                }
+
+                impl<'a, T> IntoIterator for &'a PersistentList<T> {
+                    type Item = &'a T;
+                    type IntoIter = Iter<'a, T>;
+
+                    fn into_iter(self) -> Iter<'a, T> {
+                        self.iter()
+                    }
+                }
+            };
+        }
+
+        /// `Rc` で共有する永続リスト（シングルスレッド用）
+        pub mod rc {
+            use std::rc::Rc;
+
+            persistent_list!(Rc);
+        }
+
+        /// `Arc` で共有する永続リスト（スレッド間で共有できる）
+        pub mod sync {
+            use std::sync::Arc;
+
+            persistent_list!(Arc);
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use crate::week1_basic_structures::linked_list::{ArcPersistentList, PersistentList};
+            use std::rc::Rc;
+
+            fn to_vec<T: Clone>(list: &PersistentList<T>) -> Vec<T> {
+                list.iter().cloned().collect()
+            }
+
+            #[test]
+            fn test_prepend_shares_tail() {
+                let empty = PersistentList::new();
+                let a = empty.prepend(1);
+                let b = a.prepend(2);
+                let c = a.prepend(3);
+
+                // b と c は a を共有しつつ、それぞれ別の先頭を持つ
+                assert_eq!(to_vec(&b), vec![2, 1]);
+                assert_eq!(to_vec(&c), vec![3, 1]);
+                assert_eq!(to_vec(&a), vec![1]);
+                assert!(b.tail().ptr_eq(&a));
+                assert!(c.tail().ptr_eq(&a));
+                assert!(empty.is_empty());
+            }
+
+            #[test]
+            fn test_head_tail_len() {
+                let list: PersistentList<i32> = (1..=3).collect();
+                assert_eq!(list.len(), 3);
+                assert_eq!(list.head(), Some(&1));
+
+                let tail = list.tail();
+                assert_eq!(tail.len(), 2);
+                assert_eq!(tail.head(), Some(&2));
+                assert_eq!(to_vec(&tail.tail()), vec![3]);
+
+                let empty = tail.tail().tail();
+                assert!(empty.is_empty());
+                assert_eq!(empty.head(), None);
+                assert!(empty.tail().is_empty());
+                assert_eq!(empty.tail().len(), 0);
+
+                // 元のリストは変わらない
+                assert_eq!(to_vec(&list), vec![1, 2, 3]);
+            }
+
+            #[test]
+            fn test_clone_is_shallow() {
+                let list: PersistentList<String> =
+                    ["a", "b"].iter().map(|s| s.to_string()).collect();
+                let snapshot = list.clone();
+                assert!(snapshot.ptr_eq(&list));
+                assert_eq!(snapshot, list);
+                assert_eq!(format!("{:?}", snapshot), r#"["a", "b"]"#);
+                assert_ne!(snapshot.prepend("c".to_string()), list);
+            }
+
+            #[test]
+            fn test_undo_history() {
+                // 各操作の後の状態をスナップショットとして残しておき、tail で元に戻す
+                let mut history = PersistentList::new();
+                for state in ["", "h", "he", "hel"] {
+                    history = history.prepend(state);
+                }
+                assert_eq!(history.head(), Some(&"hel"));
+
+                let undone = history.tail();
+                assert_eq!(undone.head(), Some(&"he"));
+                // 戻した後に別の編集をしても、元の履歴はそのまま残る
+                let redone = undone.prepend("hex");
+                assert_eq!(
+                    redone.iter().copied().collect::<Vec<_>>(),
+                    ["hex", "he", "h", ""]
+                );
+                assert_eq!(
+                    history.iter().copied().collect::<Vec<_>>(),
+                    ["hel", "he", "h", ""]
+                );
+            }
+
+            #[test]
+            fn test_drop_keeps_shared_tail() {
+                let marker = Rc::new(());
+                let mut base = PersistentList::new();
+                for _ in 0..5 {
+                    base = base.prepend(Rc::clone(&marker));
+                }
+                let longer = base.prepend(Rc::clone(&marker)).prepend(Rc::clone(&marker));
+                assert_eq!(Rc::strong_count(&marker), 8);
+
+                // longer 独自の 2 ノードだけが解放される
+                drop(longer);
+                assert_eq!(Rc::strong_count(&marker), 6);
+
+                let tail = base.tail();
+                drop(base);
+                assert_eq!(Rc::strong_count(&marker), 5);
+                assert_eq!(tail.len(), 4);
+                drop(tail);
+                assert_eq!(Rc::strong_count(&marker), 1);
+            }
+
+            #[test]
+            fn test_drop_long_list_on_small_stack() {
+                let handle = std::thread::Builder::new()
+                    .stack_size(64 * 1024)
+                    .spawn(|| {
+                        let mut list = PersistentList::new();
+                        for i in 0..1_000_000 {
+                            list = list.prepend(i);
+                        }
+                        // 長い共有末尾を持つリストを先に捨てても、後から捨てても溢れない
+                        let shared = list.tail();
+                        drop(list);
+                        assert_eq!(shared.len(), 999_999);
+                        drop(shared);
+                    })
+                    .unwrap();
+                handle.join().unwrap();
+            }
+
+            #[test]
+            fn test_concurrent_drop_of_shared_arc_list() {
+                use std::sync::{Arc, Barrier};
+
+                // 2 つのスレッドが同じ先頭ノードを同時に捨てる状況を作る
+                let drop_concurrently = |len: u32| {
+                    let list: ArcPersistentList<u32> = (0..len).collect();
+                    let barrier = Arc::new(Barrier::new(2));
+                    let handles: Vec<_> = [list.clone(), list]
+                        .into_iter()
+                        .map(|list| {
+                            let barrier = Arc::clone(&barrier);
+                            // 再帰的に解放されるとこのスタックでは溢れる
+                            std::thread::Builder::new()
+                                .stack_size(64 * 1024)
+                                .spawn(move || {
+                                    barrier.wait();
+                                    drop(list);
+                                })
+                                .unwrap()
+                        })
+                        .collect();
+                    for handle in handles {
+                        handle.join().unwrap();
+                    }
+                };
+
+                // 競合はまれにしか起きないので数回試す
+                for _ in 0..3 {
+                    drop_concurrently(1_000_000);
+                }
+            }
+
+            #[test]
+            fn test_arc_list_across_threads() {
+                let base: ArcPersistentList<u32> = (0..100).collect();
+                let handles: Vec<_> = (0..4)
+                    .map(|i| {
+                        let base = base.clone();
+                        std::thread::spawn(move || {
+                            let extended = base.prepend(1000 + i);
+                            assert!(extended.tail().ptr_eq(&base));
+                            extended.iter().sum::<u32>()
+                        })
+                    })
+                    .collect();
+
+                let sums: Vec<u32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+                let base_sum: u32 = (0..100).sum();
+                assert_eq!(
+                    sums,
+                    (0..4).map(|i| base_sum + 1000 + i).collect::<Vec<_>>()
+                );
+                assert_eq!(base.len(), 100);
+            }
+        }
+    }
+
+    /// アリーナ（MyVec）上に確保したノードを u32 のインデックスでつなぐ連結リスト
+    pub mod arena {
+        use crate::week1_basic_structures::my_vec::MyVec;
+        use std::fmt;
+        use std::iter::FusedIterator;
+        use std::mem;
+
+        /// 「つながっていない」ことを表すインデックス
+        const NIL: u32 = u32::MAX;
+        /// 自動 compact を行う最小のスロット数（小さいアリーナで何度も並べ直さないため）
+        const AUTO_COMPACT_MIN_SLOTS: usize = 64;
+
+        /// ArenaList の要素を指すハンドル
+        ///
+        /// 他の要素を挿入・削除しても有効なまま。指していた要素が削除されると無効になり、
+        /// 同じスロットが再利用されても世代番号が違うので誤って別の要素を指すことはない。
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct Handle {
+            index: u32,
+            generation: u32,
+        }
+
+        /// アリーナのスロット（使用中のノードか、空きリストの一部）
+        #[derive(Clone)]
+        enum Slot<T> {
+            Occupied {
+                value: T,
+                prev: u32,
+                next: u32,
+                generation: u32,
+            },
+            Free {
+                next_free: u32,
+            },
+        }
+
+        /// アリーナを使った双方向連結リスト
+        ///
+        /// ノードは 1 本の MyVec に並べて確保し、前後のノードを u32 のインデックスで指す。
+        /// 削除したスロットは空きリストにつないで次の挿入で再利用する。
+        /// unsafe を使わずに O(1) の挿入・削除ができ、Box を使うリストより
+        /// ノードがメモリ上で近くに並ぶ。
+        ///
+        /// # compact
+        /// 削除を繰り返すと空きスロットが残るので、メモリを回収したいときは `compact` を呼ぶ。
+        /// compact するとそれまでのハンドルはすべて無効になり、戻り値の `Remap` で変換する。
+        /// ハンドルを持ち続ける用途（LRU や索引）のため、デフォルトでは自動では compact しない。
+        /// `set_auto_compact(true)` にすると、`remove` / `pop_front` / `pop_back` の後、
+        /// スロット数が 64 以上で、かつ空きスロットが使用中のスロットより多くなったら
+        /// 自動で compact する（変換表は `take_remap` で受け取る）。
+        ///
+        /// # Examples
+        /// ```
+        /// let mut list = ArenaList::new();
+        /// let a = list.push_back("a");
+        /// let b = list.push_back("b");
+        /// list.push_front("c");
+        /// assert_eq!(list.remove(a), Some("a"));
+        /// assert_eq!(list.get(b), Some(&"b"));
+        /// ```
+        pub struct ArenaList<T> {
+            slots: MyVec<Slot<T>>,
+            head: u32,
+            tail: u32,
+            free_head: u32,       // 空きスロットのリストの先頭
+            len: usize,           // 使用中のスロット数
+            next_generation: u32, // 次に確保するノードの世代番号
+            auto_compact: bool,   // 削除後に自動で compact するかどうか
+            // 自動 compact で無効になったハンドルの変換表（take_remap で取り出すまで合成していく）
+            pending_remap: Option<Remap>,
+        }
+
+        impl<T> ArenaList<T> {
+            /// 新しい空のリストを作成
+            pub fn new() -> Self {
+                Self::with_capacity(0)
+            }
+
+            /// 指定した数のノードを再確保なしで持てる空のリストを作成
+            pub fn with_capacity(capacity: usize) -> Self {
+                ArenaList {
+                    slots: MyVec::with_capacity(capacity),
+                    head: NIL,
+                    tail: NIL,
+                    free_head: NIL,
+                    len: 0,
+                    next_generation: 0,
+                    auto_compact: false,
+                    pending_remap: None,
+                }
+            }
+
+            /// 要素数
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            /// 空かどうか
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// アリーナのスロット数（使用中 + 空き）
+            pub fn slot_count(&self) -> usize {
+                self.slots.len()
+            }
+
+            /// 空きスロットの数（compact で回収できる数）
+            pub fn free_slots(&self) -> usize {
+                self.slots.len() - self.len
+            }
+
+            /// 先頭に要素を追加し、そのハンドルを返す（O(1)）
+            pub fn push_front(&mut self, value: T) -> Handle {
+                let index = self.allocate(value);
+                self.link(index, NIL, self.head);
+                self.handle_at(index)
+            }
+
+            /// 末尾に要素を追加し、そのハンドルを返す（O(1)）
+            pub fn push_back(&mut self, value: T) -> Handle {
+                let index = self.allocate(value);
+                self.link(index, self.tail, NIL);
+                self.handle_at(index)
+            }
+
+            /// handle の要素の前に挿入する（O(1)）
+            ///
+            /// # Panics
+            /// handle が無効な場合
+            pub fn insert_before(&mut self, handle: Handle, value: T) -> Handle {
+                let next = self.resolve(handle).expect("invalid handle");
+                let (prev, _) = self.links(next);
+                let index = self.allocate(value);
+                self.link(index, prev, next);
+                self.handle_at(index)
+            }
+
+            /// handle の要素の後に挿入する（O(1)）
+            ///
+            /// # Panics
+            /// handle が無効な場合
+            pub fn insert_after(&mut self, handle: Handle, value: T) -> Handle {
+                let prev = self.resolve(handle).expect("invalid handle");
+                let (_, next) = self.links(prev);
+                let index = self.allocate(value);
+                self.link(index, prev, next);
+                self.handle_at(index)
+            }
+
+            /// handle の要素を削除して返す（O(1)、無効なハンドルなら None）
+            ///
+            /// 自動 compact を有効にしている場合は、ここで compact することがある（型の説明を参照）。
+            pub fn remove(&mut self, handle: Handle) -> Option<T> {
+                let index = self.resolve(handle)?;
+                Some(self.remove_at(index))
+            }
+
+            /// 先頭の要素を削除して返す（O(1)、自動 compact は remove と同じ）
+            pub fn pop_front(&mut self) -> Option<T> {
+                (self.head != NIL).then(|| self.remove_at(self.head))
+            }
+
+            /// 末尾の要素を削除して返す（O(1)、自動 compact は remove と同じ）
+            pub fn pop_back(&mut self) -> Option<T> {
+                (self.tail != NIL).then(|| self.remove_at(self.tail))
+            }
+
+            /// 削除後の自動 compact を有効・無効にする（デフォルトは無効）
+            ///
+            /// 有効にすると、削除のたびにそれまでのハンドルが無効になりうる。
+            /// 変換表は `take_remap` で取り出すまで合成され続けるので、こまめに取り出すこと。
+            pub fn set_auto_compact(&mut self, enabled: bool) {
+                self.auto_compact = enabled;
+            }
+
+            /// 削除後に自動で compact するかどうか
+            pub fn auto_compact(&self) -> bool {
+                self.auto_compact
+            }
+
+            /// 前回 take_remap を呼んでから自動 compact が起きていれば、その変換表を取り出す
+            ///
+            /// 自動 compact が複数回起きた場合も、最初の compact より前のハンドルを
+            /// 最新のハンドルに直接変換できるよう合成してある。
+            pub fn take_remap(&mut self) -> Option<Remap> {
+                self.pending_remap.take()
+            }
+
+            /// handle が有効（要素がまだリストにある）かどうか
+            pub fn contains(&self, handle: Handle) -> bool {
+                self.resolve(handle).is_some()
+            }
+
+            /// handle の要素を参照
+            pub fn get(&self, handle: Handle) -> Option<&T> {
+                let index = self.resolve(handle)?;
+                match &self.slots[index as usize] {
+                    Slot::Occupied { value, .. } => Some(value),
+                    Slot::Free { .. } => None,
+                }
+            }
+
+            /// handle の要素を可変参照
+            pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+                let index = self.resolve(handle)?;
+                match &mut self.slots[index as usize] {
+                    Slot::Occupied { value, .. } => Some(value),
+                    Slot::Free { .. } => None,
+                }
+            }
+
+            /// 先頭の要素のハンドル
+            pub fn front(&self) -> Option<Handle> {
+                (self.head != NIL).then(|| self.handle_at(self.head))
+            }
+
+            /// 末尾の要素のハンドル
+            pub fn back(&self) -> Option<Handle> {
+                (self.tail != NIL).then(|| self.handle_at(self.tail))
+            }
+
+            /// handle の次の要素のハンドル
+            pub fn next(&self, handle: Handle) -> Option<Handle> {
+                let (_, next) = self.links(self.resolve(handle)?);
+                (next != NIL).then(|| self.handle_at(next))
+            }
+
+            /// handle の前の要素のハンドル
+            pub fn prev(&self, handle: Handle) -> Option<Handle> {
+                let (prev, _) = self.links(self.resolve(handle)?);
+                (prev != NIL).then(|| self.handle_at(prev))
+            }
+
+            /// すべての要素を削除（これまでのハンドルはすべて無効になる）
+            pub fn clear(&mut self) {
+                self.slots.clear();
+                self.head = NIL;
+                self.tail = NIL;
+                self.free_head = NIL;
+                self.len = 0;
+                self.pending_remap = None;
+            }
+
+            /// 先頭から順に要素への参照を返すイテレータ（両端から取り出せる）
+            pub fn iter(&self) -> Iter<'_, T> {
+                Iter {
+                    slots: &self.slots,
+                    front: self.head,
+                    back: self.tail,
+                    remaining: self.len,
+                }
+            }
+
+            /// 空きスロットを詰め、ノードをリストの順にアリーナへ並べ直す（O(n)）
+            ///
+            /// 削除を繰り返して空きが増えたときに定期的に呼ぶと、メモリを回収でき、
+            /// 先頭から辿るときのメモリアクセスも連続になる。
+            /// ノードの位置が変わるので、それまでのハンドルはすべて無効になる。
+            /// 古いハンドルは戻り値の `Remap` で新しいハンドルに変換できる。
+            pub fn compact(&mut self) -> Remap {
+                let remap = self.compact_slots();
+                // 自動 compact の変換表が未回収なら、この compact の分も合成しておく
+                if let Some(pending) = self.pending_remap.take() {
+                    self.pending_remap = Some(pending.then(&remap));
+                }
+                remap
+            }
+
+            /// compact の本体（並べ直して変換表を返す）
+            fn compact_slots(&mut self) -> Remap {
+                let mut old_slots = mem::replace(&mut self.slots, MyVec::with_capacity(self.len));
+                let mut map = vec![None; old_slots.len()];
+
+                let mut current = self.head;
+                let mut new_index = 0;
+                while current != NIL {
+                    let slot = mem::replace(
+                        &mut old_slots[current as usize],
+                        Slot::Free { next_free: NIL },
+                    );
+                    let Slot::Occupied {
+                        value,
+                        next,
+                        generation: old_generation,
+                        ..
+                    } = slot
+                    else {
+                        unreachable!("linked slot must be occupied");
+                    };
+
+                    // 並べ直したノードは前後の番号が連続する
+                    let generation = self.fresh_generation();
+                    self.slots.push(Slot::Occupied {
+                        value,
+                        prev: if new_index == 0 { NIL } else { new_index - 1 },
+                        next: if next == NIL { NIL } else { new_index + 1 },
+                        generation,
+                    });
+                    map[current as usize] = Some((
+                        old_generation,
+                        Handle {
+                            index: new_index,
+                            generation,
+                        },
+                    ));
+
+                    current = next;
+                    new_index += 1;
+                }
+
+                self.head = if self.len == 0 { NIL } else { 0 };
+                self.tail = if self.len == 0 { NIL } else { new_index - 1 };
+                self.free_head = NIL;
+                Remap { map }
+            }
+
+            /// index のノードを削除し、空きスロットが増えすぎていれば compact する
+            fn remove_at(&mut self, index: u32) -> T {
+                let value = self.unlink(index);
+                if self.auto_compact
+                    && self.slots.len() >= AUTO_COMPACT_MIN_SLOTS
+                    && self.free_slots() > self.len
+                {
+                    let remap = self.compact_slots();
+                    self.pending_remap = Some(match self.pending_remap.take() {
+                        Some(pending) => pending.then(&remap),
+                        None => remap,
+                    });
+                }
+                value
+            }
+
+            /// 値をスロットに置き、そのインデックスを返す（空きスロットがあれば再利用）
+            fn allocate(&mut self, value: T) -> u32 {
+                let slot = Slot::Occupied {
+                    value,
+                    prev: NIL,
+                    next: NIL,
+                    generation: self.fresh_generation(),
+                };
+                if self.free_head != NIL {
+                    let index = self.free_head;
+                    let old = mem::replace(&mut self.slots[index as usize], slot);
+                    let Slot::Free { next_free } = old else {
+                        unreachable!("free list must point to a free slot");
+                    };
+                    self.free_head = next_free;
+                    index
+                } else {
+                    let index = self.slots.len();
+                    assert!(
+                        index < NIL as usize,
+                        "ArenaList cannot hold more than u32::MAX - 1 nodes"
+                    );
+                    self.slots.push(slot);
+                    index as u32
+                }
+            }
+
+            /// index のノードを prev と next の間につなぐ
+            fn link(&mut self, index: u32, prev: u32, next: u32) {
+                let (prev_link, next_link) = self.links_mut(index);
+                *prev_link = prev;
+                *next_link = next;
+                if prev == NIL {
+                    self.head = index;
+                } else {
+                    *self.links_mut(prev).1 = index;
+                }
+                if next == NIL {
+                    self.tail = index;
+                } else {
+                    *self.links_mut(next).0 = index;
+                }
+                self.len += 1;
+            }
+
+            /// index のノードを切り離し、スロットを空きリストに戻して値を返す
+            fn unlink(&mut self, index: u32) -> T {
+                let slot = mem::replace(
+                    &mut self.slots[index as usize],
+                    Slot::Free {
+                        next_free: self.free_head,
+                    },
+                );
+                self.free_head = index;
+                let Slot::Occupied {
+                    value, prev, next, ..
+                } = slot
+                else {
+                    unreachable!("linked slot must be occupied");
+                };
+
+                if prev == NIL {
+                    self.head = next;
+                } else {
+                    *self.links_mut(prev).1 = next;
+                }
+                if next == NIL {
+                    self.tail = prev;
+                } else {
+                    *self.links_mut(next).0 = prev;
+                }
+                self.len -= 1;
+                value
+            }
+
+            /// ハンドルが有効ならスロットのインデックスを返す
+            fn resolve(&self, handle: Handle) -> Option<u32> {
+                match self.slots.get(handle.index as usize) {
+                    Some(Slot::Occupied { generation, .. }) if *generation == handle.generation => {
+                        Some(handle.index)
+                    }
+                    _ => None,
+                }
+            }
+
+            /// 使用中のスロットのハンドル
+            fn handle_at(&self, index: u32) -> Handle {
+                match &self.slots[index as usize] {
+                    Slot::Occupied { generation, .. } => Handle {
+                        index,
+                        generation: *generation,
+                    },
+                    Slot::Free { .. } => unreachable!("linked slot must be occupied"),
+                }
+            }
+
+            /// 使用中のスロットの (prev, next)
+            fn links(&self, index: u32) -> (u32, u32) {
+                match &self.slots[index as usize] {
+                    Slot::Occupied { prev, next, .. } => (*prev, *next),
+                    Slot::Free { .. } => unreachable!("linked slot must be occupied"),
+                }
+            }
+
+            /// 使用中のスロットの (prev, next) を書き換える
+            fn links_mut(&mut self, index: u32) -> (&mut u32, &mut u32) {
+                match &mut self.slots[index as usize] {
+                    Slot::Occupied { prev, next, .. } => (prev, next),
+                    Slot::Free { .. } => unreachable!("linked slot must be occupied"),
+                }
+            }
+
+            /// ノードごとに新しい世代番号を振る（u32 を一周するまで重複しない）
+            fn fresh_generation(&mut self) -> u32 {
+                let generation = self.next_generation;
+                self.next_generation = generation.wrapping_add(1);
+                generation
+            }
+        }
+
+        /// `ArenaList::compact`（または自動 compact）で無効になった古いハンドルを、新しいハンドルに変換する表
+        #[derive(Debug, Clone)]
+        pub struct Remap {
+            // 古いインデックス -> (古い世代番号, 新しいハンドル)
+            map: Vec<Option<(u32, Handle)>>,
+        }
+
+        impl Remap {
+            /// compact 前に有効だったハンドルの移動先（compact 前から無効だったなら None）
+            pub fn get(&self, old: Handle) -> Option<Handle> {
+                match self.map.get(old.index as usize) {
+                    Some(&Some((generation, new))) if generation == old.generation => Some(new),
+                    _ => None,
+                }
+            }
+
+            /// self の後に next の compact が行われたときの、通しの変換表
+            fn then(self, next: &Remap) -> Remap {
+                let map = self
+                    .map
+                    .into_iter()
+                    .map(|entry| {
+                        let (generation, middle) = entry?;
+                        Some((generation, next.get(middle)?))
+                    })
+                    .collect();
+                Remap { map }
+            }
+        }
+
+        impl<T> Default for ArenaList<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T: Clone> Clone for ArenaList<T> {
+            /// スロットの配置ごと複製するので、元のリストのハンドルがそのまま使える
+            fn clone(&self) -> Self {
+                ArenaList {
+                    slots: self.slots.clone(),
+                    head: self.head,
+                    tail: self.tail,
+                    free_head: self.free_head,
+                    len: self.len,
+                    next_generation: self.next_generation,
+                    auto_compact: self.auto_compact,
+                    pending_remap: self.pending_remap.clone(),
+                }
+            }
+        }
+
+        impl<T: fmt::Debug> fmt::Debug for ArenaList<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<T: PartialEq> PartialEq for ArenaList<T> {
+            /// 要素の並びだけを比べる（スロットの配置やハンドルは問わない）
+            fn eq(&self, other: &Self) -> bool {
+                self.len == other.len && self.iter().eq(other.iter())
+            }
+        }
+
+        impl<T: Eq> Eq for ArenaList<T> {}
+
+        impl<T> FromIterator<T> for ArenaList<T> {
+            fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+                let mut list = ArenaList::new();
+                list.extend(iter);
+                list
+            }
+        }
+
+        impl<T> Extend<T> for ArenaList<T> {
+            fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+                for value in iter {
+                    self.push_back(value);
+                }
+            }
+        }
+
+        /// 要素への参照を返す両方向イテレータ（`ArenaList::iter` で作る）
+        pub struct Iter<'a, T> {
+            slots: &'a MyVec<Slot<T>>,
+            front: u32,
+            back: u32,
+            remaining: usize,
+        }
+
+        impl<'a, T> Iter<'a, T> {
+            fn slot(&self, index: u32) -> (&'a T, u32, u32) {
+                match &self.slots[index as usize] {
+                    Slot::Occupied {
+                        value, prev, next, ..
+                    } => (value, *prev, *next),
+                    Slot::Free { .. } => unreachable!("linked slot must be occupied"),
+                }
+            }
+        }
+
+        impl<'a, T> Iterator for Iter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<&'a T> {
+                if self.remaining == 0 {
+                    return None;
+                }
+                let (value, _, next) = self.slot(self.front);
+                self.front = next;
+                self.remaining -= 1;
+                Some(value)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                (self.remaining, Some(self.remaining))
+            }
+        }
+
+        impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+            fn next_back(&mut self) -> Option<&'a T> {
+                if self.remaining == 0 {
+                    return None;
+                }
+                let (value, prev, _) = self.slot(self.back);
+                self.back = prev;
+                self.remaining -= 1;
+                Some(value)
+            }
+        }
+
+        impl<T> ExactSizeIterator for Iter<'_, T> {}
+        impl<T> FusedIterator for Iter<'_, T> {}
+
+        impl<'a, T> IntoIterator for &'a ArenaList<T> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+
+            fn into_iter(self) -> Iter<'a, T> {
+                self.iter()
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use crate::week1_basic_structures::linked_list::ArenaList;
+
+            fn to_vec<T: Clone>(list: &ArenaList<T>) -> Vec<T> {
+                list.iter().cloned().collect()
+            }
+
+            #[test]
+            fn test_push_pop_both_ends() {
+                let mut list = ArenaList::new();
+                assert_eq!(list.pop_front(), None);
+                assert_eq!(list.pop_back(), None);
+
+                list.push_back(2);
+                list.push_back(3);
+                list.push_front(1);
+                assert_eq!(to_vec(&list), vec![1, 2, 3]);
+                assert_eq!(
+                    list.iter().rev().copied().collect::<Vec<_>>(),
+                    vec![3, 2, 1]
+                );
+
+                assert_eq!(list.pop_front(), Some(1));
+                assert_eq!(list.pop_back(), Some(3));
+                assert_eq!(list.pop_back(), Some(2));
+                assert!(list.is_empty());
+                assert_eq!(list.front(), None);
+                assert_eq!(list.back(), None);
+            }
+
+            #[test]
+            fn test_handles_stay_valid_across_insertions() {
+                let mut list = ArenaList::new();
+                let first = list.push_back(0);
+                let handles: Vec<_> = (1..1000).map(|i| list.push_back(i)).collect();
+                list.push_front(-1);
+
+                // アリーナが何度再確保されても、ハンドルは同じ要素を指し続ける
+                assert_eq!(list.get(first), Some(&0));
+                assert_eq!(list.get(handles[499]), Some(&500));
+                *list.get_mut(handles[0]).unwrap() = 100;
+                assert_eq!(list.next(first).and_then(|h| list.get(h)), Some(&100));
+            }
+
+            #[test]
+            fn test_remove_by_handle() {
+                let mut list = ArenaList::new();
+                let a = list.push_back("a");
+                let b = list.push_back("b");
+                let c = list.push_back("c");
+
+                assert_eq!(list.remove(b), Some("b"));
+                assert_eq!(to_vec(&list), vec!["a", "c"]);
+                assert_eq!(list.next(a), Some(c));
+                assert_eq!(list.prev(c), Some(a));
+
+                // 削除済みのハンドルは無効
+                assert!(!list.contains(b));
+                assert_eq!(list.remove(b), None);
+                assert_eq!(list.get(b), None);
+
+                assert_eq!(list.remove(c), Some("c"));
+                assert_eq!(list.back(), Some(a));
+                assert_eq!(list.remove(a), Some("a"));
+                assert!(list.is_empty());
+            }
+
+            #[test]
+            fn test_free_list_reuses_slots() {
+                let mut list = ArenaList::new();
+                let handles: Vec<_> = (0..4).map(|i| list.push_back(i)).collect();
+                list.remove(handles[1]);
+                list.remove(handles[2]);
+                assert_eq!(list.free_slots(), 2);
+
+                // 空きスロットが再利用されるので、アリーナは大きくならない
+                let x = list.push_back(10);
+                let y = list.push_front(11);
+                assert_eq!(list.slot_count(), 4);
+                assert_eq!(list.free_slots(), 0);
+                assert_eq!(to_vec(&list), vec![11, 0, 3, 10]);
+
+                // 同じスロットを再利用しても、古いハンドルは新しい要素を指さない
+                assert_eq!(list.get(handles[1]), None);
+                assert_eq!(list.get(handles[2]), None);
+                assert_eq!(list.get(x), Some(&10));
+                assert_eq!(list.get(y), Some(&11));
+            }
+
+            #[test]
+            fn test_insert_before_after() {
+                let mut list = ArenaList::new();
+                let b = list.push_back('b');
+                list.insert_before(b, 'a');
+                let d = list.insert_after(b, 'd');
+                list.insert_before(d, 'c');
+                list.insert_after(d, 'e');
+                assert_eq!(to_vec(&list), vec!['a', 'b', 'c', 'd', 'e']);
+                assert_eq!(list.front().and_then(|h| list.get(h)), Some(&'a'));
+                assert_eq!(list.back().and_then(|h| list.get(h)), Some(&'e'));
+            }
+
+            #[test]
+            #[should_panic(expected = "invalid handle")]
+            fn test_insert_with_stale_handle() {
+                let mut list = ArenaList::new();
+                let a = list.push_back(1);
+                list.remove(a);
+                list.insert_after(a, 2);
+            }
+
+            #[test]
+            fn test_compact() {
+                let mut list = ArenaList::new();
+                let handles: Vec<_> = (0..10).map(|i| list.push_back(i)).collect();
+                for &h in handles.iter().step_by(2) {
+                    list.remove(h);
+                }
+                let front = list.push_front(-1);
+                assert_eq!(list.slot_count(), 10);
+
+                let remap = list.compact();
+                assert_eq!(list.slot_count(), 6);
+                assert_eq!(list.free_slots(), 0);
+                assert_eq!(to_vec(&list), vec![-1, 1, 3, 5, 7, 9]);
+
+                // 古いハンドルは無効になり、Remap で新しいハンドルに変換できる
+                assert_eq!(list.get(handles[3]), None);
+                let new_three = remap.get(handles[3]).unwrap();
+                assert_eq!(list.get(new_three), Some(&3));
+                assert_eq!(list.get(remap.get(front).unwrap()), Some(&-1));
+                // compact 前に削除されていたハンドルは変換できない
+                assert_eq!(remap.get(handles[4]), None);
+
+                // compact 後も通常どおり操作できる
+                list.insert_after(new_three, 4);
+                list.push_back(10);
+                assert_eq!(to_vec(&list), vec![-1, 1, 3, 4, 5, 7, 9, 10]);
+                assert_eq!(list.iter().rev().count(), 8);
+
+                let mut empty: ArenaList<i32> = ArenaList::new();
+                let h = empty.push_back(1);
+                empty.remove(h);
+                empty.compact();
+                assert_eq!(empty.slot_count(), 0);
+                empty.push_back(2);
+                assert_eq!(to_vec(&empty), vec![2]);
+            }
+
+            #[test]
+            fn test_auto_compact_on_remove() {
+                let mut list: ArenaList<u32> = ArenaList::new();
+                list.set_auto_compact(true);
+                let handles: Vec<_> = (0..100).map(|i| list.push_back(i)).collect();
+                let last = handles[99];
+
+                // 49 個削除するまでは空きスロット（49）が使用中（51）より少ない
+                for &h in &handles[..49] {
+                    list.remove(h);
+                }
+                assert_eq!(list.slot_count(), 100);
+                assert!(list.take_remap().is_none());
+                assert!(list.contains(last));
+
+                // 51 個目を削除すると空き 51 > 使用中 49 となり、自動で compact される
+                list.remove(handles[49]);
+                list.remove(handles[50]);
+                assert_eq!(list.slot_count(), 49);
+                assert_eq!(list.free_slots(), 0);
+                assert_eq!(
+                    list.iter().copied().collect::<Vec<_>>(),
+                    (51..100).collect::<Vec<_>>()
+                );
+
+                // 古いハンドルは無効になり、変換表で新しいハンドルに変換できる
+                assert!(!list.contains(last));
+                let remap = list.take_remap().unwrap();
+                let new_last = remap.get(last).unwrap();
+                assert_eq!(list.get(new_last), Some(&99));
+                assert_eq!(list.back(), Some(new_last));
+                assert_eq!(remap.get(handles[0]), None);
+                assert!(list.take_remap().is_none());
+            }
+
+            #[test]
+            fn test_auto_compact_remaps_compose() {
+                let mut list: ArenaList<u32> = (0..200).collect();
+                list.set_auto_compact(true);
+                let last = list.back().unwrap();
+                // 先頭から削除し続けると、スロット数 200 → 99 → 49 と 2 回自動 compact される
+                for _ in 0..170 {
+                    list.pop_front();
+                }
+                assert_eq!(list.len(), 30);
+                assert!(list.slot_count() < 64);
+
+                // 2 回分の変換表が合成されているので、最初のハンドルから直接たどれる
+                let remap = list.take_remap().unwrap();
+                assert_eq!(list.get(remap.get(last).unwrap()), Some(&199));
+            }
+
+            #[test]
+            fn test_auto_compact_disabled_by_default() {
+                let mut list: ArenaList<u32> = ArenaList::new();
+                assert!(!list.auto_compact());
+                let handles: Vec<_> = (0..100).map(|i| list.push_back(i)).collect();
+                for &h in &handles[..90] {
+                    list.remove(h);
+                }
+                // 空きが増えてもハンドルはそのまま使える
+                assert_eq!(list.slot_count(), 100);
+                assert_eq!(list.get(handles[95]), Some(&95));
+                assert!(list.take_remap().is_none());
+
+                list.set_auto_compact(true);
+                list.set_auto_compact(false);
+                list.pop_front();
+                assert_eq!(list.slot_count(), 100);
+            }
+
+            #[test]
+            fn test_clear_invalidates_handles() {
+                let mut list: ArenaList<i32> = (0..3).collect();
+                let front = list.front().unwrap();
+                list.clear();
+                assert!(list.is_empty());
+                let new_front = list.push_back(5);
+                assert_eq!(list.get(front), None);
+                assert_eq!(list.get(new_front), Some(&5));
+            }
+
+            #[test]
+            fn test_clone_keeps_handles() {
+                let mut list = ArenaList::new();
+                let a = list.push_back(String::from("a"));
+                list.push_back(String::from("b"));
+                let cloned = list.clone();
+
+                assert_eq!(cloned, list);
+                assert_eq!(cloned.get(a).map(String::as_str), Some("a"));
+                list.remove(a);
+                assert_ne!(cloned, list);
+                assert_eq!(format!("{:?}", list), r#"["b"]"#);
+            }
+
+            #[test]
+            fn test_lru_bookkeeping() {
+                // 使った要素を先頭へ移し、溢れたら末尾から捨てる
+                let mut lru = ArenaList::new();
+                let a = lru.push_front("a");
+                lru.push_front("b");
+                lru.push_front("c");
+
+                let key = lru.remove(a).unwrap();
+                let a = lru.push_front(key);
+                assert_eq!(to_vec(&lru), vec!["a", "c", "b"]);
+                assert_eq!(lru.front(), Some(a));
+                assert_eq!(lru.pop_back(), Some("b"));
+            }
+        }
+    }
+
+    /// 複数レベルの連結リストで順序付きマップを作るスキップリスト
+    ///
+    /// `LinkedList` のノード（next を 1 本だけ持つ）をそのまま使うのではなく、
+    /// 同じ「ノード + 次へのリンク」の形を、リンクをレベルの数だけ持つ形に広げている。
+    /// ノードは `ArenaList` と同じくインデックスでつなぐ。1 つのノードが各レベルの
+    /// 前のノードから同時に指されるので、所有者が 1 つに決まる Box の鎖では表せず、
+    /// 生ポインタにすると削除時の付け替えがすべて unsafe になる。インデックスなら
+    /// 複数の前ノードから安全に指せて、削除したスロットも再利用できる。
+    pub mod skip_list {
+        use std::borrow::Borrow;
+        use std::cmp::Ordering;
+        use std::fmt;
+        use std::iter::FusedIterator;
+        use std::mem;
+        use std::ops::{Bound, RangeBounds};
+
+        /// 「つながっていない」ことを表すインデックス
+        const NIL: usize = usize::MAX;
+        /// 番兵（先頭）ノードのインデックス
+        const HEAD: usize = 0;
+        /// レベルの上限（2^32 要素程度までは期待計算量が保たれる）
+        const MAX_LEVEL: usize = 32;
+
+        /// SplitMix64 による疑似乱数生成器
+        ///
+        /// 同じシードからは同じ列が得られるので、テストを再現できる。
+        /// 暗号用途には使えない。
+        #[derive(Debug, Clone)]
+        pub struct SplitMix64 {
+            state: u64,
+        }
+
+        impl SplitMix64 {
+            /// シードを指定して作成（どんな値でもよい）
+            pub fn new(seed: u64) -> Self {
+                SplitMix64 { state: seed }
+            }
+
+            /// 次の 64 ビットの乱数
+            pub fn next_u64(&mut self) -> u64 {
+                self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                let mut z = self.state;
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                z ^ (z >> 31)
+            }
+        }
+
+        /// ノード（forward[i] はレベル i での次のノード）
+        ///
+        /// 番兵ノードと削除済みのスロットは entry が None。
+        struct Node<K, V> {
+            entry: Option<(K, V)>,
+            forward: Vec<usize>,
+        }
+
+        /// スキップリストによる順序付きマップ
+        ///
+        /// レベル 0 は全ノードをキー順につないだ単方向連結リストで、上のレベルほど
+        /// ノードを間引いた「急行」になっている。各ノードの高さは確率 1/2 で 1 つずつ
+        /// 伸ばして決めるので、探索・挿入・削除の期待計算量は O(log n)。
+        ///
+        /// ノードは Vec 上に確保し、インデックスでつなぐ（削除したスロットは再利用する）。
+        ///
+        /// # Examples
+        /// ```
+        /// let mut map = SkipList::with_seed(42);
+        /// map.insert(3, "c");
+        /// map.insert(1, "a");
+        /// map.insert(2, "b");
+        /// assert_eq!(map.get(&2), Some(&"b"));
+        /// assert_eq!(map.range(2..).map(|(k, _)| *k).collect::<Vec<_>>(), vec![2, 3]);
+        /// ```
+        pub struct SkipList<K, V> {
+            nodes: Vec<Node<K, V>>, // nodes[HEAD] は番兵
+            free: Vec<usize>,       // 削除済みで再利用できるスロット
+            level: usize,           // 使用中の最大レベル（1 以上）
+            len: usize,
+            rng: SplitMix64,
+        }
+
+        impl<K: Ord, V> SkipList<K, V> {
+            /// 新しい空のマップを作成（固定のシードを使う）
+            pub fn new() -> Self {
+                Self::with_seed(0x5eed)
+            }
+
+            /// レベル決定に使う乱数のシードを指定して作成
+            pub fn with_seed(seed: u64) -> Self {
+                SkipList {
+                    nodes: vec![Node {
+                        entry: None,
+                        forward: vec![NIL; MAX_LEVEL],
+                    }],
+                    free: Vec::new(),
+                    level: 1,
+                    len: 0,
+                    rng: SplitMix64::new(seed),
+                }
+            }
+
+            /// 要素数
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            /// 空かどうか
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// キーと値を挿入する（期待 O(log n)）
+            ///
+            /// 既にキーがあれば値を置き換え、古い値を返す。
+            pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+                let (update, candidate) = self.find(&key);
+                if candidate != NIL && self.key(candidate) == &key {
+                    let (_, old) = self.nodes[candidate].entry.as_mut().expect("occupied");
+                    return Some(mem::replace(old, value));
+                }
+
+                // 新しいノードの高さを決め、使用中のレベルより高ければ番兵から伸ばす
+                let height = self.random_level();
+                if height > self.level {
+                    self.level = height;
+                }
+
+                let index = self.allocate(key, value, height);
+                for (level, &prev) in update.iter().enumerate().take(height) {
+                    self.nodes[index].forward[level] = self.nodes[prev].forward[level];
+                    self.nodes[prev].forward[level] = index;
+                }
+                self.len += 1;
+                None
+            }
+
+            /// キーに対応する値を参照（期待 O(log n)）
+            pub fn get<Q>(&self, key: &Q) -> Option<&V>
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+            {
+                let index = self.find_index(key)?;
+                self.nodes[index].entry.as_ref().map(|(_, v)| v)
+            }
+
+            /// キーに対応する値を可変参照（期待 O(log n)）
+            pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+            {
+                let index = self.find_index(key)?;
+                self.nodes[index].entry.as_mut().map(|(_, v)| v)
+            }
+
+            /// キーが存在するか
+            pub fn contains_key<Q>(&self, key: &Q) -> bool
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+            {
+                self.find_index(key).is_some()
+            }
+
+            /// キーを削除し、その値を返す（期待 O(log n)）
+            pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+            {
+                let (update, candidate) = self.find(key);
+                if candidate == NIL || self.key(candidate).borrow() != key {
+                    return None;
+                }
+
+                // 削除するノードを指していた各レベルのリンクを付け替える
+                let forward = mem::take(&mut self.nodes[candidate].forward);
+                for (level, next) in forward.into_iter().enumerate() {
+                    self.nodes[update[level]].forward[level] = next;
+                }
+                // 空になった上のレベルを下げる
+                while self.level > 1 && self.nodes[HEAD].forward[self.level - 1] == NIL {
+                    self.level -= 1;
+                }
+
+                let (_, value) = self.nodes[candidate].entry.take().expect("occupied");
+                self.free.push(candidate);
+                self.len -= 1;
+                Some(value)
+            }
+
+            /// 最小のキーと値
+            pub fn first_key_value(&self) -> Option<(&K, &V)> {
+                self.entry(self.nodes[HEAD].forward[0])
+            }
+
+            /// すべての要素を削除
+            pub fn clear(&mut self) {
+                self.nodes.truncate(1);
+                self.nodes[HEAD].forward.fill(NIL);
+                self.free.clear();
+                self.level = 1;
+                self.len = 0;
+            }
+
+            /// キーの昇順に (キー, 値) を返すイテレータ
+            pub fn iter(&self) -> Iter<'_, K, V> {
+                Iter {
+                    nodes: &self.nodes,
+                    current: self.nodes[HEAD].forward[0],
+                    end: NIL,
+                }
+            }
+
+            /// 範囲内のキーを昇順に返すイテレータ（端の探索は期待 O(log n)）
+            ///
+            /// # Panics
+            /// 範囲の始点が終点より大きい場合、または始点と終点が等しくどちらも除外の場合
+            pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+                R: RangeBounds<Q>,
+            {
+                match (range.start_bound(), range.end_bound()) {
+                    (Bound::Excluded(start), Bound::Excluded(end)) if start == end => {
+                        panic!("range start and end are equal and excluded in SkipList")
+                    }
+                    (
+                        Bound::Included(start) | Bound::Excluded(start),
+                        Bound::Included(end) | Bound::Excluded(end),
+                    ) if start > end => panic!("range start is greater than range end in SkipList"),
+                    _ => {}
+                }
+
+                // 始点: 範囲に入る最初のノード、終点: 範囲を出た最初のノード
+                let current = match range.start_bound() {
+                    Bound::Included(start) => self.lower_bound(start, true),
+                    Bound::Excluded(start) => self.lower_bound(start, false),
+                    Bound::Unbounded => self.nodes[HEAD].forward[0],
+                };
+                let end = match range.end_bound() {
+                    Bound::Included(end) => self.lower_bound(end, false),
+                    Bound::Excluded(end) => self.lower_bound(end, true),
+                    Bound::Unbounded => NIL,
+                };
+                Iter {
+                    nodes: &self.nodes,
+                    current,
+                    end,
+                }
+            }
+
+            /// 各レベルで key の直前にあるノードと、レベル 0 で直後にあるノードを求める
+            fn find<Q>(&self, key: &Q) -> ([usize; MAX_LEVEL], usize)
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+            {
+                let mut update = [HEAD; MAX_LEVEL];
+                let mut x = HEAD;
+                // 上のレベルから、key を越えない範囲で右へ進んでは 1 つ下りる
+                for level in (0..self.level).rev() {
+                    loop {
+                        let next = self.nodes[x].forward[level];
+                        if next != NIL && self.key(next).borrow() < key {
+                            x = next;
+                        } else {
+                            break;
+                        }
+                    }
+                    update[level] = x;
+                }
+                (update, self.nodes[x].forward[0])
+            }
+
+            /// key を持つノードのインデックス
+            fn find_index<Q>(&self, key: &Q) -> Option<usize>
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+            {
+                let (_, candidate) = self.find(key);
+                (candidate != NIL && self.key(candidate).borrow() == key).then_some(candidate)
+            }
+
+            /// key 以上（inclusive が false なら key より大きい）最初のノード
+            fn lower_bound<Q>(&self, key: &Q, inclusive: bool) -> usize
+            where
+                K: Borrow<Q>,
+                Q: Ord + ?Sized,
+            {
+                let mut x = HEAD;
+                for level in (0..self.level).rev() {
+                    loop {
+                        let next = self.nodes[x].forward[level];
+                        if next == NIL {
+                            break;
+                        }
+                        let advance = match self.key(next).borrow().cmp(key) {
+                            Ordering::Less => true,
+                            Ordering::Equal => !inclusive,
+                            Ordering::Greater => false,
+                        };
+                        if !advance {
+                            break;
+                        }
+                        x = next;
+                    }
+                }
+                self.nodes[x].forward[0]
+            }
+
+            /// 新しいノードの高さ（確率 1/2 で 1 段ずつ高くなる）
+            fn random_level(&mut self) -> usize {
+                // 乱数の下位ビットに続く 0 の数は、確率 1/2^k で k 以上になる
+                let zeros = self.rng.next_u64().trailing_zeros() as usize;
+                (zeros + 1).min(MAX_LEVEL)
+            }
+
+            /// ノードを確保してインデックスを返す（削除済みのスロットがあれば再利用）
+            fn allocate(&mut self, key: K, value: V, height: usize) -> usize {
+                let node = Node {
+                    entry: Some((key, value)),
+                    forward: vec![NIL; height],
+                };
+                match self.free.pop() {
+                    Some(index) => {
+                        self.nodes[index] = node;
+                        index
+                    }
+                    None => {
+                        self.nodes.push(node);
+                        self.nodes.len() - 1
+                    }
+                }
+            }
+        }
+
+        impl<K, V> SkipList<K, V> {
+            /// 使用中のノードのキー
+            fn key(&self, index: usize) -> &K {
+                &self.nodes[index]
+                    .entry
+                    .as_ref()
+                    .expect("linked node must be occupied")
+                    .0
+            }
+
+            /// 使用中のノードの (キー, 値)（index が NIL なら None）
+            fn entry(&self, index: usize) -> Option<(&K, &V)> {
+                if index == NIL {
+                    return None;
+                }
+                self.nodes[index].entry.as_ref().map(|(k, v)| (k, v))
+            }
+        }
+
+        impl<K: Ord, V> Default for SkipList<K, V> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<K: Ord, V> FromIterator<(K, V)> for SkipList<K, V> {
+            fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+                let mut map = SkipList::new();
+                map.extend(iter);
+                map
+            }
+        }
+
+        impl<K: Ord, V> Extend<(K, V)> for SkipList<K, V> {
+            fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+                for (key, value) in iter {
+                    self.insert(key, value);
+                }
+            }
+        }
+
+        impl<K: Ord + fmt::Debug, V: fmt::Debug> fmt::Debug for SkipList<K, V> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_map().entries(self.iter()).finish()
+            }
+        }
+
+        impl<'a, K: Ord, V> IntoIterator for &'a SkipList<K, V> {
+            type Item = (&'a K, &'a V);
+            type IntoIter = Iter<'a, K, V>;
+
+            fn into_iter(self) -> Iter<'a, K, V> {
+                self.iter()
+            }
+        }
+
+        /// (キー, 値) をキーの昇順に返すイテレータ（`iter` と `range` で作る）
+        pub struct Iter<'a, K, V> {
+            nodes: &'a [Node<K, V>],
+            current: usize,
+            end: usize, // このノードに着いたら終了（NIL なら最後まで）
+        }
+
+        impl<'a, K, V> Iterator for Iter<'a, K, V> {
+            type Item = (&'a K, &'a V);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.current == self.end || self.current == NIL {
+                    return None;
+                }
+                let node = &self.nodes[self.current];
+                self.current = node.forward[0];
+                node.entry.as_ref().map(|(k, v)| (k, v))
+            }
+        }
+
+        impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use std::collections::BTreeMap;
+
+            fn keys(iter: Iter<'_, i32, i32>) -> Vec<i32> {
+                iter.map(|(k, _)| *k).collect()
+            }
+
+            #[test]
+            fn test_split_mix_64_sequence() {
+                let mut rng = SplitMix64::new(0);
+                assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+                assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+                assert_eq!(rng.next_u64(), 0x06c4_5d18_8009_454f);
+            }
+
+            #[test]
+            fn test_insert_get_remove() {
+                let mut map = SkipList::new();
+                assert_eq!(map.insert("b", 2), None);
+                assert_eq!(map.insert("a", 1), None);
+                assert_eq!(map.insert("c", 3), None);
+                assert_eq!(map.insert("b", 20), Some(2));
+                assert_eq!(map.len(), 3);
+
+                assert_eq!(map.get("b"), Some(&20));
+                assert_eq!(map.get("z"), None);
+                *map.get_mut("a").unwrap() += 10;
+                assert_eq!(map.first_key_value(), Some((&"a", &11)));
+
+                assert_eq!(map.remove("b"), Some(20));
+                assert_eq!(map.remove("b"), None);
+                assert!(!map.contains_key("b"));
+                assert_eq!(map.len(), 2);
+                assert_eq!(format!("{:?}", map), r#"{"a": 11, "c": 3}"#);
+
+                map.clear();
+                assert!(map.is_empty());
+                assert_eq!(map.first_key_value(), None);
+                map.insert("x", 0);
+                assert_eq!(map.iter().count(), 1);
+            }
+
+            #[test]
+            fn test_borrowed_key_lookup() {
+                let mut map = SkipList::new();
+                map.insert(String::from("apple"), 1);
+                map.insert(String::from("banana"), 2);
+                // String のキーを &str で検索できる
+                assert_eq!(map.get("banana"), Some(&2));
+                assert_eq!(
+                    map.range::<str, _>((Bound::Included("apple"), Bound::Excluded("banana")))
+                        .count(),
+                    1
+                );
+            }
+
+            #[test]
+            fn test_range() {
+                let map: SkipList<i32, i32> = (0..20).map(|i| (i * 2, i)).collect();
+
+                assert_eq!(keys(map.range(4..10)), vec![4, 6, 8]);
+                assert_eq!(keys(map.range(5..=10)), vec![6, 8, 10]);
+                assert_eq!(keys(map.range(..3)), vec![0, 2]);
+                assert_eq!(keys(map.range(35..)), vec![36, 38]);
+                assert_eq!(
+                    keys(map.range((Bound::Excluded(4), Bound::Excluded(8)))),
+                    vec![6]
+                );
+                assert_eq!(keys(map.range(100..)), Vec::<i32>::new());
+                assert_eq!(keys(map.range(5..5)), Vec::<i32>::new());
+                assert_eq!(keys(map.range(6..6)), Vec::<i32>::new());
+                assert_eq!(map.range(..).count(), 20);
+            }
+
+            #[test]
+            #[should_panic(expected = "range start is greater than range end")]
+            fn test_range_start_after_end() {
+                let map: SkipList<i32, ()> = SkipList::new();
+                let _ = map.range((Bound::Included(5), Bound::Excluded(3)));
+            }
+
+            #[test]
+            fn test_same_seed_same_structure() {
+                fn heights(seed: u64) -> Vec<usize> {
+                    let mut map = SkipList::with_seed(seed);
+                    for i in 0..100 {
+                        map.insert(i, ());
+                    }
+                    map.nodes.iter().map(|node| node.forward.len()).collect()
+                }
+
+                assert_eq!(heights(7), heights(7));
+                assert_ne!(heights(7), heights(8));
+            }
+
+            #[test]
+            fn test_level_is_logarithmic() {
+                let n = 100_000;
+                let map: SkipList<u32, ()> = (0..n).map(|i| (i, ())).collect();
+
+                // 最大レベルは log2(n) ≈ 17 程度、平均の高さは 2 程度になる
+                assert!(
+                    (10..=MAX_LEVEL).contains(&map.level),
+                    "level = {}",
+                    map.level
+                );
+                let total: usize = map.nodes[1..].iter().map(|node| node.forward.len()).sum();
+                let average = total as f64 / n as f64;
+                assert!(
+                    (1.8..2.2).contains(&average),
+                    "average height = {}",
+                    average
+                );
+            }
+
+            #[test]
+            fn test_against_btree_map() {
+                let mut rng = SplitMix64::new(2024);
+                let mut map = SkipList::with_seed(1);
+                let mut expected = BTreeMap::new();
+
+                for step in 0..20_000 {
+                    let key = (rng.next_u64() % 500) as i32;
+                    match rng.next_u64() % 4 {
+                        0 | 1 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                        2 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                        _ => assert_eq!(map.get(&key), expected.get(&key)),
+                    }
+                    assert_eq!(map.len(), expected.len());
+
+                    if step % 1000 == 0 {
+                        assert!(map.iter().eq(expected.iter()));
+                        let a = (rng.next_u64() % 500) as i32;
+                        let b = a + (rng.next_u64() % 100) as i32;
+                        assert!(map.range(a..b).eq(expected.range(a..b)));
+                        assert!(map.range(a..=b).eq(expected.range(a..=b)));
+                        assert!(map.range(..a).eq(expected.range(..a)));
+                    }
+                }
+                assert!(map.iter().eq(expected.iter()));
+                assert_eq!(map.first_key_value(), expected.first_key_value());
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_new() {
+            let list: LinkedList<i32> = LinkedList::new();
+            assert_eq!(list.len(), 0);
+            assert!(list.is_empty());
+        }
+
+        #[test]
+        fn test_push_front() {
+            let mut list = LinkedList::new();
+            list.push_front(1);
+            list.push_front(2);
+            list.push_front(3);
+
+            assert_eq!(list.len(), 3);
+            assert_eq!(list.peek(), Some(&3));
+        }
+
+        #[test]
+        fn test_pop_front() {
+            let mut list = LinkedList::new();
+            list.push_front(1);
+            list.push_front(2);
+
+            assert_eq!(list.pop_front(), Some(2));
+            assert_eq!(list.pop_front(), Some(1));
+            assert_eq!(list.pop_front(), None);
+        }
+
+        #[test]
+        fn test_push_back_and_peek_back() {
+            let mut list = LinkedList::new();
+            assert_eq!(list.peek_back(), None);
+
+            list.push_back(1);
+            assert_eq!(list.peek(), Some(&1));
+            assert_eq!(list.peek_back(), Some(&1));
+
+            list.push_back(2);
+            list.push_front(0);
+            list.push_back(3);
+
+            assert_eq!(list.len(), 4);
+            assert_eq!(list.peek(), Some(&0));
+            assert_eq!(list.peek_back(), Some(&3));
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+        }
+
+        #[test]
+        fn test_tail_valid_through_pop_front() {
+            let mut list = LinkedList::new();
+            for i in 0..3 {
+                list.push_back(i);
+            }
+
+            // 末尾以外を取り出しても tail は変わらない
+            assert_eq!(list.pop_front(), Some(0));
+            assert_eq!(list.peek_back(), Some(&2));
+            assert_eq!(list.pop_front(), Some(1));
+            assert_eq!(list.peek_back(), Some(&2));
+
+            // 最後の 1 つを取り出すと空になり、tail もリセットされる
+            assert_eq!(list.pop_front(), Some(2));
+            assert!(list.is_empty());
+            assert_eq!(list.peek_back(), None);
+            assert_eq!(list.pop_front(), None);
+
+            // 空になった後も push_back / push_front で正しく再構築できる
+            list.push_back(10);
+            assert_eq!(list.peek(), Some(&10));
+            assert_eq!(list.peek_back(), Some(&10));
+            assert_eq!(list.pop_front(), Some(10));
+
+            list.push_front(20);
+            assert_eq!(list.peek_back(), Some(&20));
+            list.push_back(21);
+            assert_eq!(list.peek_back(), Some(&21));
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![20, 21]);
+        }
+
+        #[test]
+        fn test_fifo_large() {
+            // push_back が O(n) だと 10^5 要素で二乗時間になる
+            let n = 100_000;
+            let mut list = LinkedList::new();
+            for i in 0..n {
+                list.push_back(i);
+            }
+            assert_eq!(list.len(), n);
+            assert_eq!(list.peek_back(), Some(&(n - 1)));
+
+            for i in 0..n {
+                assert_eq!(list.pop_front(), Some(i));
+            }
+            assert!(list.is_empty());
+            assert_eq!(list.peek_back(), None);
+        }
+
+        #[test]
+        fn test_drop_long_list_on_small_stack() {
+            // 再帰的な drop だと、64KiB のスタックでは 100 万ノードを解放できない
+            let handle = std::thread::Builder::new()
+                .stack_size(64 * 1024)
+                .spawn(|| {
+                    let mut list = LinkedList::new();
+                    for i in 0..1_000_000 {
+                        list.push_back(i);
+                    }
+                    assert_eq!(list.len(), 1_000_000);
+                    drop(list);
+                })
+                .unwrap();
+            handle.join().unwrap();
+        }
+
+        #[test]
+        fn test_iter_mut() {
+            let mut list: LinkedList<i32> = (1..=4).collect();
+            assert_eq!(list.iter_mut().len(), 4);
+            for x in list.iter_mut() {
+                *x *= 10;
+            }
+            for x in &mut list {
+                *x += 1;
+            }
+            assert_eq!(
+                list.iter().copied().collect::<Vec<_>>(),
+                vec![11, 21, 31, 41]
+            );
+        }
+
+        #[test]
+        fn test_into_iter() {
+            let list: LinkedList<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+
+            let borrowed: Vec<&String> = (&list).into_iter().collect();
+            assert_eq!(borrowed, vec!["a", "b", "c"]);
+
+            let mut iter = list.into_iter();
+            assert_eq!(iter.len(), 3);
+            assert_eq!(iter.next().as_deref(), Some("a"));
+            assert_eq!(iter.size_hint(), (2, Some(2)));
+            // 残りの要素は IntoIter の drop で解放される
+        }
+
+        #[test]
+        fn test_from_iter_and_extend() {
+            let mut list: LinkedList<i32> = vec![1, 2].into_iter().collect();
+            list.extend(vec![3, 4]);
+            list.extend(&[5]);
+
+            assert_eq!(list.len(), 5);
+            assert_eq!(list.peek_back(), Some(&5));
+            assert_eq!(
+                list.iter().copied().collect::<Vec<_>>(),
+                vec![1, 2, 3, 4, 5]
+            );
+
+            // 空のリストへの extend でも tail が正しく設定される
+            let mut empty = LinkedList::new();
+            empty.extend(0..3);
+            assert_eq!(empty.peek_back(), Some(&2));
+        }
+
+        #[test]
+        fn test_clone_and_eq() {
+            let list: LinkedList<i32> = (0..5).collect();
+            let mut cloned = list.clone();
+            assert_eq!(list, cloned);
+
+            cloned.push_back(5);
+            assert_ne!(list, cloned);
+            assert_eq!(cloned.peek_back(), Some(&5));
+            assert_eq!(list.peek_back(), Some(&4));
+
+            let other: LinkedList<i32> = (1..6).collect();
+            assert_ne!(list, other);
+        }
+
+        #[test]
+        fn test_debug_and_display() {
+            let list: LinkedList<i32> = (1..=3).collect();
+            assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+            assert_eq!(format!("{}", list), "1 -> 2 -> 3 -> None");
+
+            let empty: LinkedList<i32> = LinkedList::new();
+            assert_eq!(format!("{:?}", empty), "[]");
+            assert_eq!(empty.to_string(), "None");
+        }
+
+        fn to_vec<T: Clone>(list: &LinkedList<T>) -> Vec<T> {
+            list.iter().cloned().collect()
+        }
+
+        #[test]
+        fn test_reverse() {
+            let mut list: LinkedList<i32> = (1..=4).collect();
+            list.reverse();
+            assert_eq!(to_vec(&list), vec![4, 3, 2, 1]);
+            assert_eq!(list.peek_back(), Some(&1));
+
+            // 反転後も tail が有効なので push_back できる
+            list.push_back(0);
+            assert_eq!(to_vec(&list), vec![4, 3, 2, 1, 0]);
+
+            let mut empty: LinkedList<i32> = LinkedList::new();
+            empty.reverse();
+            assert!(empty.is_empty());
+            assert_eq!(empty.peek_back(), None);
+
+            let mut single: LinkedList<i32> = (0..1).collect();
+            single.reverse();
+            assert_eq!(single.peek(), single.peek_back());
+        }
+
+        #[test]
+        fn test_split_off() {
+            let mut list: LinkedList<i32> = (0..5).collect();
+            let mut back = list.split_off(2);
+            assert_eq!(to_vec(&list), vec![0, 1]);
+            assert_eq!(to_vec(&back), vec![2, 3, 4]);
+            assert_eq!(list.len(), 2);
+            assert_eq!(back.len(), 3);
+            assert_eq!(list.peek_back(), Some(&1));
+            assert_eq!(back.peek_back(), Some(&4));
+
+            // 端での分割
+            let all = list.split_off(0);
+            assert!(list.is_empty());
+            assert_eq!(list.peek_back(), None);
+            assert_eq!(to_vec(&all), vec![0, 1]);
+
+            let none = back.split_off(3);
+            assert!(none.is_empty());
+            assert_eq!(none.peek_back(), None);
+            back.push_back(5);
+            assert_eq!(to_vec(&back), vec![2, 3, 4, 5]);
+        }
+
+        #[test]
+        #[should_panic(expected = "split_off index")]
+        fn test_split_off_out_of_bounds() {
+            let mut list: LinkedList<i32> = (0..3).collect();
+            list.split_off(4);
+        }
+
+        #[test]
+        fn test_append() {
+            let mut a: LinkedList<i32> = (0..3).collect();
+            let mut b: LinkedList<i32> = (3..5).collect();
+            a.append(&mut b);
+            assert_eq!(to_vec(&a), vec![0, 1, 2, 3, 4]);
+            assert_eq!(a.len(), 5);
+            assert_eq!(a.peek_back(), Some(&4));
+            assert!(b.is_empty());
+            assert_eq!(b.peek_back(), None);
+
+            // 空のリストとの連結
+            a.append(&mut b);
+            assert_eq!(a.len(), 5);
+            let mut empty = LinkedList::new();
+            empty.append(&mut a);
+            assert_eq!(to_vec(&empty), vec![0, 1, 2, 3, 4]);
+            assert_eq!(empty.peek_back(), Some(&4));
+
+            // 連結した後も両方のリストを使い続けられる
+            empty.push_back(5);
+            a.push_back(10);
+            assert_eq!(empty.peek_back(), Some(&5));
+            assert_eq!(to_vec(&a), vec![10]);
+        }
+
+        #[test]
+        fn test_remove_if() {
+            let mut list: LinkedList<i32> = (0..10).collect();
+            assert_eq!(list.remove_if(|x| x % 3 == 0), 4);
+            assert_eq!(to_vec(&list), vec![1, 2, 4, 5, 7, 8]);
+            assert_eq!(list.len(), 6);
+            assert_eq!(list.peek_back(), Some(&8));
+
+            // 末尾を消しても tail が正しく更新される
+            assert_eq!(list.remove_if(|&x| x > 4), 3);
+            assert_eq!(list.peek_back(), Some(&4));
+            list.push_back(100);
+            assert_eq!(to_vec(&list), vec![1, 2, 4, 100]);
+
+            assert_eq!(list.remove_if(|_| true), 4);
+            assert!(list.is_empty());
+            assert_eq!(list.peek_back(), None);
+        }
+
+        #[test]
+        fn test_sort() {
+            let mut list: LinkedList<i32> = vec![5, 2, 8, 1, 9, 3, 7, 4].into_iter().collect();
+            list.sort();
+            assert_eq!(to_vec(&list), vec![1, 2, 3, 4, 5, 7, 8, 9]);
+            assert_eq!(list.peek_back(), Some(&9));
+            assert_eq!(list.len(), 8);
+
+            list.sort_by(|a, b| b.cmp(a));
+            assert_eq!(to_vec(&list), vec![9, 8, 7, 5, 4, 3, 2, 1]);
+
+            let mut empty: LinkedList<i32> = LinkedList::new();
+            empty.sort();
+            assert!(empty.is_empty());
+        }
+
+        #[test]
+        fn test_sort_is_stable_without_clone() {
+            // Clone できない要素でも並べ替えられる
+            #[derive(Debug)]
+            struct Item {
+                key: u8,
+                id: usize,
+            }
+
+            let mut list: LinkedList<Item> = [3, 1, 2, 1, 3, 2, 1]
+                .iter()
+                .enumerate()
+                .map(|(id, &key)| Item { key, id })
+                .collect();
+            list.sort_by(|a, b| a.key.cmp(&b.key));
+
+            let order: Vec<(u8, usize)> = list.iter().map(|item| (item.key, item.id)).collect();
+            // 同じキーの要素は元の順序（id の昇順）を保つ
+            assert_eq!(
+                order,
+                vec![(1, 1), (1, 3), (1, 6), (2, 2), (2, 5), (3, 0), (3, 4)]
+            );
+        }
+
+        #[test]
+        fn test_sort_matches_vec_sort() {
+            // 線形合同法で疑似乱数列を作り、Vec::sort と結果を比べる
+            let mut seed: u64 = 42;
+            let values: Vec<u32> = (0..10_000)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    (seed >> 33) as u32 % 1000
+                })
+                .collect();
+
+            let mut list: LinkedList<u32> = values.iter().copied().collect();
+            list.sort();
+            let mut expected = values;
+            expected.sort();
+
+            assert_eq!(to_vec(&list), expected);
+            assert_eq!(list.peek_back(), expected.last());
+        }
+
+        #[test]
+        fn test_drop_runs_element_destructors() {
+            use std::rc::Rc;
+
+            let marker = Rc::new(());
+            let mut list = LinkedList::new();
+            for _ in 0..10 {
+                list.push_back(Rc::clone(&marker));
+            }
+            assert_eq!(Rc::strong_count(&marker), 11);
+            drop(list);
+            assert_eq!(Rc::strong_count(&marker), 1);
+        }
+    }
+}
+
+// ---------------------------------------------------------
+// 課題2-2: 双方向連結リスト
+// ---------------------------------------------------------
+
+pub mod doubly_linked_list {
+    use std::fmt;
+    use std::iter::FusedIterator;
+    use std::marker::PhantomData;
+    use std::mem;
+    use std::ptr::NonNull;
+
+    type Link<T> = Option<NonNull<Node<T>>>;
+    /// リストから切り離したノードの鎖（先頭, 末尾, ノード数）
+    type Chain<T> = (NonNull<Node<T>>, NonNull<Node<T>>, usize);
+
+    /// ノード構造（前後のノードへのポインタを持つ）
+    struct Node<T> {
+        data: T,
+        prev: Link<T>,
+        next: Link<T>,
+    }
+
+    /// 双方向連結リスト
+    ///
+    /// 各ノードは Box で確保したものを生ポインタとして保持し、リスト自身が所有する。
+    /// 先頭・末尾の両方で push/pop が O(1) で行え、`CursorMut` を使うと
+    /// 途中の位置での挿入・削除・分割・連結も O(1) で行える。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut list = DoublyLinkedList::new();
+    /// list.push_back(2);
+    /// list.push_front(1);
+    /// assert_eq!(list.pop_back(), Some(2));
+    /// ```
+    pub struct DoublyLinkedList<T> {
+        head: Link<T>,
+        tail: Link<T>,
+        len: usize,
+        // Box<Node<T>> を所有していることをコンパイラに伝える（drop check のため）
+        _marker: PhantomData<Box<Node<T>>>,
+    }
+
+    // ノードはすべてリストが所有しているので、Box<Node<T>> と同じ条件で送受信できる
+    unsafe impl<T: Send> Send for DoublyLinkedList<T> {}
+    unsafe impl<T: Sync> Sync for DoublyLinkedList<T> {}
+
+    impl<T> DoublyLinkedList<T> {
+        /// 新しい空のリストを作成
+        pub fn new() -> Self {
+            DoublyLinkedList {
+                head: None,
+                tail: None,
+                len: 0,
+                _marker: PhantomData,
+            }
+        }
+
+        /// 要素数
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.head.is_none()
+        }
+
+        /// 先頭に要素を追加（O(1)）
+        pub fn push_front(&mut self, data: T) {
+            let node = Self::new_node(data);
+            // SAFETY: node は確保したばかりでどのリストにもつながっていない
+            unsafe { self.splice_between(None, self.head, node, node, 1) };
+        }
+
+        /// 末尾に要素を追加（O(1)）
+        pub fn push_back(&mut self, data: T) {
+            let node = Self::new_node(data);
+            // SAFETY: node は確保したばかりでどのリストにもつながっていない
+            unsafe { self.splice_between(self.tail, None, node, node, 1) };
+        }
+
+        /// 先頭の要素を削除して返す（O(1)）
+        pub fn pop_front(&mut self) -> Option<T> {
+            // SAFETY: head は self が所有するノードを指す
+            self.head.map(|node| unsafe { self.unlink(node) })
+        }
+
+        /// 末尾の要素を削除して返す（O(1)）
+        pub fn pop_back(&mut self) -> Option<T> {
+            // SAFETY: tail は self が所有するノードを指す
+            self.tail.map(|node| unsafe { self.unlink(node) })
+        }
+
+        /// 先頭の要素を参照
+        pub fn front(&self) -> Option<&T> {
+            // SAFETY: head は self が所有するノードを指し、&self の間は変更されない
+            self.head.map(|node| unsafe { &(*node.as_ptr()).data })
+        }
+
+        /// 先頭の要素を可変参照
+        pub fn front_mut(&mut self) -> Option<&mut T> {
+            // SAFETY: &mut self の間、このノードへの参照は他に存在しない
+            self.head.map(|node| unsafe { &mut (*node.as_ptr()).data })
+        }
+
+        /// 末尾の要素を参照
+        pub fn back(&self) -> Option<&T> {
+            // SAFETY: front と同じ
+            self.tail.map(|node| unsafe { &(*node.as_ptr()).data })
+        }
+
+        /// 末尾の要素を可変参照
+        pub fn back_mut(&mut self) -> Option<&mut T> {
+            // SAFETY: front_mut と同じ
+            self.tail.map(|node| unsafe { &mut (*node.as_ptr()).data })
+        }
+
+        /// すべての要素を削除
+        pub fn clear(&mut self) {
+            while self.pop_front().is_some() {}
+        }
+
+        /// 先頭から順に要素への参照を返すイテレータ（両端から取り出せる）
+        pub fn iter(&self) -> Iter<'_, T> {
+            Iter {
+                head: self.head,
+                tail: self.tail,
+                remaining: self.len,
+                _marker: PhantomData,
+            }
+        }
+
+        /// 先頭から順に要素への可変参照を返すイテレータ（両端から取り出せる）
+        pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+            IterMut {
+                head: self.head,
+                tail: self.tail,
+                remaining: self.len,
+                _marker: PhantomData,
+            }
+        }
+
+        /// 先頭の要素を指すカーソル（空なら「要素の外」を指す）
+        pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+            CursorMut {
+                current: self.head,
+                index: 0,
+                list: self,
+            }
+        }
+
+        /// 末尾の要素を指すカーソル（空なら「要素の外」を指す）
+        pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+            CursorMut {
+                current: self.tail,
+                index: self.len.saturating_sub(1),
+                list: self,
+            }
+        }
+
+        /// ノードをヒープに確保して、所有権を生ポインタとして取り出す
+        fn new_node(data: T) -> NonNull<Node<T>> {
+            let node = Box::new(Node {
+                data,
+                prev: None,
+                next: None,
+            });
+            NonNull::from(Box::leak(node))
+        }
+
+        /// first..=last の鎖（count 個のノード）を prev と next の間につなぐ
+        ///
+        /// prev が None なら先頭に、next が None なら末尾につなぐ。
+        ///
+        /// # Safety
+        /// - prev と next は self の隣り合ったノード（またはリストの端）であること
+        /// - first..=last は count 個のノードからなる、どのリストにも属さない鎖であること
+        unsafe fn splice_between(
+            &mut self,
+            prev: Link<T>,
+            next: Link<T>,
+            first: NonNull<Node<T>>,
+            last: NonNull<Node<T>>,
+            count: usize,
+        ) {
+            (*first.as_ptr()).prev = prev;
+            (*last.as_ptr()).next = next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).next = Some(first),
+                None => self.head = Some(first),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).prev = Some(last),
+                None => self.tail = Some(last),
+            }
+            self.len += count;
+        }
+
+        /// ノードをリストから切り離し、解放して中身を返す
+        ///
+        /// # Safety
+        /// node は self が所有するノードであること
+        unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+            let node = Box::from_raw(node.as_ptr());
+            match node.prev {
+                Some(prev) => (*prev.as_ptr()).next = node.next,
+                None => self.head = node.next,
+            }
+            match node.next {
+                Some(next) => (*next.as_ptr()).prev = node.prev,
+                None => self.tail = node.prev,
+            }
+            self.len -= 1;
+            node.data
+        }
+
+        /// すべてのノードを鎖ごと取り出し、self を空にする（ノードは解放しない）
+        fn detach_all(&mut self) -> Option<Chain<T>> {
+            let head = self.head.take()?;
+            let tail = self.tail.take()?;
+            Some((head, tail, mem::replace(&mut self.len, 0)))
+        }
+    }
+
+    impl<T> Default for DoublyLinkedList<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl<T> Drop for DoublyLinkedList<T> {
+        /// ループで 1 つずつ解放する（再帰しないので長いリストでもスタックが溢れない）
+        fn drop(&mut self) {
+            self.clear();
+        }
+    }
+
+    /// リスト上の位置を指し、その場での挿入・削除を行うカーソル
+    ///
+    /// 末尾と先頭の間には「要素の外（ghost）」という位置があり、リストを環状に移動できる。
+    /// ghost を指しているとき `current` は None を返す。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut list: DoublyLinkedList<i32> = (1..=3).collect();
+    /// let mut cursor = list.cursor_front_mut();
+    /// cursor.move_next();
+    /// assert_eq!(cursor.remove_current(), Some(2));
+    /// cursor.insert_before(20);
+    /// assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 20, 3]);
+    /// ```
+    pub struct CursorMut<'a, T> {
+        current: Link<T>,
+        index: usize, // ghost を指しているときは list.len
+        list: &'a mut DoublyLinkedList<T>,
+    }
+
+    impl<'a, T> CursorMut<'a, T> {
+        /// 現在位置のインデックス（ghost なら None）
+        pub fn index(&self) -> Option<usize> {
+            self.current.map(|_| self.index)
+        }
+
+        /// 次の要素へ移動（末尾の次は ghost、ghost の次は先頭）
+        pub fn move_next(&mut self) {
+            match self.current {
+                // SAFETY: current は list が所有するノードを指す
+                Some(node) => {
+                    self.current = unsafe { (*node.as_ptr()).next };
+                    self.index += 1;
+                }
+                None => {
+                    self.current = self.list.head;
+                    self.index = 0;
+                }
+            }
+        }
+
+        /// 前の要素へ移動（先頭の前は ghost、ghost の前は末尾）
+        pub fn move_prev(&mut self) {
+            match self.current {
+                // SAFETY: current は list が所有するノードを指す
+                Some(node) => {
+                    self.current = unsafe { (*node.as_ptr()).prev };
+                    self.index = match self.current {
+                        Some(_) => self.index - 1,
+                        None => self.list.len,
+                    };
+                }
+                None => {
+                    self.current = self.list.tail;
+                    self.index = self.list.len.saturating_sub(1);
+                }
+            }
+        }
+
+        /// 現在の要素への可変参照（ghost なら None）
+        pub fn current(&mut self) -> Option<&mut T> {
+            // SAFETY: カーソルが list を可変借用しているので、他に参照は存在しない
+            self.current
+                .map(|node| unsafe { &mut (*node.as_ptr()).data })
+        }
+
+        /// 次の要素への可変参照（移動はしない）
+        pub fn peek_next(&mut self) -> Option<&mut T> {
+            let next = match self.current {
+                // SAFETY: current は list が所有するノードを指す
+                Some(node) => unsafe { (*node.as_ptr()).next },
+                None => self.list.head,
+            };
+            // SAFETY: current と同じ
+            next.map(|node| unsafe { &mut (*node.as_ptr()).data })
+        }
+
+        /// 前の要素への可変参照（移動はしない）
+        pub fn peek_prev(&mut self) -> Option<&mut T> {
+            let prev = match self.current {
+                // SAFETY: current は list が所有するノードを指す
+                Some(node) => unsafe { (*node.as_ptr()).prev },
+                None => self.list.tail,
+            };
+            // SAFETY: current と同じ
+            prev.map(|node| unsafe { &mut (*node.as_ptr()).data })
+        }
+
+        /// 現在の要素の前に挿入する（ghost なら末尾に追加）
+        pub fn insert_before(&mut self, data: T) {
+            let node = DoublyLinkedList::new_node(data);
+            let (prev, next) = self.neighbours_before();
+            // SAFETY: prev と next は隣り合っており、node はどのリストにも属さない
+            unsafe { self.list.splice_between(prev, next, node, node, 1) };
+            // 現在の要素（ghost を含む）は 1 つ後ろにずれる
+            self.index += 1;
+        }
+
+        /// 現在の要素の後に挿入する（ghost なら先頭に追加）
+        pub fn insert_after(&mut self, data: T) {
+            let node = DoublyLinkedList::new_node(data);
+            let (prev, next) = self.neighbours_after();
+            // SAFETY: insert_before と同じ
+            unsafe { self.list.splice_between(prev, next, node, node, 1) };
+            // ghost のインデックスは常に len なので追従させる
+            if self.current.is_none() {
+                self.index += 1;
+            }
+        }
+
+        /// 現在の要素を削除して返し、カーソルを次の要素へ進める（ghost なら何もしない）
+        pub fn remove_current(&mut self) -> Option<T> {
+            let node = self.current?;
+            // SAFETY: current は list が所有するノードを指す
+            unsafe {
+                self.current = (*node.as_ptr()).next;
+                // 次の要素が同じインデックスに詰められるので index はそのまま
+                Some(self.list.unlink(node))
+            }
+        }
+
+        /// 現在の要素より後ろをすべて切り離し、新しいリストとして返す（O(1)）
+        ///
+        /// ghost を指しているときはリスト全体を返す。
+        pub fn split_after(&mut self) -> DoublyLinkedList<T> {
+            let Some(node) = self.current else {
+                self.index = 0;
+                return mem::take(self.list);
+            };
+            // SAFETY: current は list が所有するノードで、その後ろのノードもすべて list のもの
+            unsafe {
+                let Some(next) = (*node.as_ptr()).next.take() else {
+                    return DoublyLinkedList::new();
+                };
+                (*next.as_ptr()).prev = None;
+                let split_len = self.list.len - self.index - 1;
+                let split = DoublyLinkedList {
+                    head: Some(next),
+                    tail: self.list.tail,
+                    len: split_len,
+                    _marker: PhantomData,
+                };
+                self.list.tail = Some(node);
+                self.list.len = self.index + 1;
+                split
+            }
+        }
+
+        /// 別のリストの要素をすべて現在の要素の後に差し込む（O(1)、ghost なら先頭に）
+        pub fn splice_after(&mut self, mut other: DoublyLinkedList<T>) {
+            let Some((first, last, count)) = other.detach_all() else {
+                return;
+            };
+            let (prev, next) = self.neighbours_after();
+            // SAFETY: first..=last は other から切り離した count 個の鎖
+            unsafe { self.list.splice_between(prev, next, first, last, count) };
+            if self.current.is_none() {
+                self.index += count;
+            }
+        }
+
+        /// 別のリストの要素をすべて現在の要素の前に差し込む（O(1)、ghost なら末尾に）
+        pub fn splice_before(&mut self, mut other: DoublyLinkedList<T>) {
+            let Some((first, last, count)) = other.detach_all() else {
+                return;
+            };
+            let (prev, next) = self.neighbours_before();
+            // SAFETY: splice_after と同じ
+            unsafe { self.list.splice_between(prev, next, first, last, count) };
+            self.index += count;
+        }
+
+        /// 現在位置の「直前」に挿入するときの前後のノード
+        fn neighbours_before(&self) -> (Link<T>, Link<T>) {
+            match self.current {
+                // SAFETY: current は list が所有するノードを指す
+                Some(node) => (unsafe { (*node.as_ptr()).prev }, Some(node)),
+                None => (self.list.tail, None),
+            }
+        }
+
+        /// 現在位置の「直後」に挿入するときの前後のノード
+        fn neighbours_after(&self) -> (Link<T>, Link<T>) {
+            match self.current {
+                // SAFETY: current は list が所有するノードを指す
+                Some(node) => (Some(node), unsafe { (*node.as_ptr()).next }),
+                None => (None, self.list.head),
+            }
+        }
+    }
+
+    /// 要素への参照を返す両方向イテレータ（`DoublyLinkedList::iter` で作る）
+    pub struct Iter<'a, T> {
+        head: Link<T>,
+        tail: Link<T>,
+        remaining: usize, // 前後から取り出したぶんを引いた残り（0 になったら終了）
+        _marker: PhantomData<&'a Node<T>>,
+    }
+
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+
+        fn next(&mut self) -> Option<&'a T> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.head.map(|node| {
+                // SAFETY: リストが &'a で借用されている間、ノードは生きていて変更されない
+                let node = unsafe { &*node.as_ptr() };
+                self.head = node.next;
+                self.remaining -= 1;
+                &node.data
+            })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.remaining, Some(self.remaining))
+        }
+    }
+
+    impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+        fn next_back(&mut self) -> Option<&'a T> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.tail.map(|node| {
+                // SAFETY: next と同じ
+                let node = unsafe { &*node.as_ptr() };
+                self.tail = node.prev;
+                self.remaining -= 1;
+                &node.data
+            })
+        }
+    }
+
+    impl<T> ExactSizeIterator for Iter<'_, T> {}
+    impl<T> FusedIterator for Iter<'_, T> {}
+
+    impl<T> Clone for Iter<'_, T> {
+        fn clone(&self) -> Self {
+            Iter { ..*self }
+        }
+    }
+
+    unsafe impl<T: Sync> Send for Iter<'_, T> {}
+    unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+    /// 要素への可変参照を返す両方向イテレータ（`DoublyLinkedList::iter_mut` で作る）
+    pub struct IterMut<'a, T> {
+        head: Link<T>,
+        tail: Link<T>,
+        remaining: usize,
+        _marker: PhantomData<&'a mut Node<T>>,
+    }
+
+    impl<'a, T> Iterator for IterMut<'a, T> {
+        type Item = &'a mut T;
+
+        fn next(&mut self) -> Option<&'a mut T> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.head.map(|node| {
+                // SAFETY: remaining で前後が交差しないようにしているので、同じ要素を 2 回返さない
+                let node = unsafe { &mut *node.as_ptr() };
+                self.head = node.next;
+                self.remaining -= 1;
+                &mut node.data
+            })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.remaining, Some(self.remaining))
+        }
+    }
+
+    impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+        fn next_back(&mut self) -> Option<&'a mut T> {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.tail.map(|node| {
+                // SAFETY: next と同じ
+                let node = unsafe { &mut *node.as_ptr() };
+                self.tail = node.prev;
+                self.remaining -= 1;
+                &mut node.data
+            })
+        }
+    }
+
+    impl<T> ExactSizeIterator for IterMut<'_, T> {}
+    impl<T> FusedIterator for IterMut<'_, T> {}
+
+    unsafe impl<T: Send> Send for IterMut<'_, T> {}
+    unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+    /// 要素を所有権ごと取り出す両方向イテレータ（`into_iter` で作る）
+    pub struct IntoIter<T> {
+        list: DoublyLinkedList<T>,
+    }
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            self.list.pop_front()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.list.len, Some(self.list.len))
+        }
+    }
+
+    impl<T> DoubleEndedIterator for IntoIter<T> {
+        fn next_back(&mut self) -> Option<T> {
+            self.list.pop_back()
+        }
+    }
+
+    impl<T> ExactSizeIterator for IntoIter<T> {}
+    impl<T> FusedIterator for IntoIter<T> {}
+
+    impl<T> IntoIterator for DoublyLinkedList<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+
+        fn into_iter(self) -> IntoIter<T> {
+            IntoIter { list: self }
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a DoublyLinkedList<T> {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+
+        fn into_iter(self) -> Iter<'a, T> {
+            self.iter()
+        }
+    }
+
+    impl<'a, T> IntoIterator for &'a mut DoublyLinkedList<T> {
+        type Item = &'a mut T;
+        type IntoIter = IterMut<'a, T>;
+
+        fn into_iter(self) -> IterMut<'a, T> {
+            self.iter_mut()
+        }
+    }
+
+    impl<T> FromIterator<T> for DoublyLinkedList<T> {
+        fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+            let mut list = DoublyLinkedList::new();
+            list.extend(iter);
+            list
+        }
+    }
+
+    impl<T> Extend<T> for DoublyLinkedList<T> {
+        fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+            for item in iter {
+                self.push_back(item);
+            }
+        }
+    }
+
+    impl<T: Clone> Clone for DoublyLinkedList<T> {
+        fn clone(&self) -> Self {
+            self.iter().cloned().collect()
+        }
+    }
+
+    impl<T: fmt::Debug> fmt::Debug for DoublyLinkedList<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_list().entries(self.iter()).finish()
+        }
+    }
+
+    impl<T: PartialEq> PartialEq for DoublyLinkedList<T> {
+        fn eq(&self, other: &Self) -> bool {
+            self.len == other.len && self.iter().eq(other.iter())
+        }
+    }
+
+    impl<T: Eq> Eq for DoublyLinkedList<T> {}
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn to_vec<T: Clone>(list: &DoublyLinkedList<T>) -> Vec<T> {
+            list.iter().cloned().collect()
+        }
+
+        /// 前後のリンクが矛盾していないことを確認する
+        fn assert_links<T: PartialEq + fmt::Debug>(list: &DoublyLinkedList<T>) {
+            let forward: Vec<&T> = list.iter().collect();
+            let mut backward: Vec<&T> = list.iter().rev().collect();
+            backward.reverse();
+            assert_eq!(forward, backward);
+            assert_eq!(forward.len(), list.len());
+            assert_eq!(list.front(), forward.first().copied());
+            assert_eq!(list.back(), forward.last().copied());
+        }
+
+        #[test]
+        fn test_push_pop_both_ends() {
+            let mut list = DoublyLinkedList::new();
+            assert!(list.is_empty());
+            assert_eq!(list.pop_front(), None);
+            assert_eq!(list.pop_back(), None);
+
+            list.push_back(2);
+            list.push_back(3);
+            list.push_front(1);
+            list.push_front(0);
+            assert_links(&list);
+            assert_eq!(to_vec(&list), vec![0, 1, 2, 3]);
+
+            assert_eq!(list.pop_back(), Some(3));
+            assert_eq!(list.pop_front(), Some(0));
+            assert_eq!(list.pop_back(), Some(2));
+            assert_eq!(list.front(), Some(&1));
+            assert_eq!(list.back(), Some(&1));
+            assert_eq!(list.pop_front(), Some(1));
+            assert!(list.is_empty());
+            assert_eq!(list.back(), None);
+
+            list.push_front(9);
+            assert_eq!(list.back(), Some(&9));
+        }
+
+        #[test]
+        fn test_front_back_mut() {
+            let mut list: DoublyLinkedList<i32> = (1..=3).collect();
+            *list.front_mut().unwrap() = 10;
+            *list.back_mut().unwrap() = 30;
+            assert_eq!(to_vec(&list), vec![10, 2, 30]);
+        }
+
+        #[test]
+        fn test_double_ended_iter() {
+            let mut list: DoublyLinkedList<i32> = (1..=5).collect();
+
+            let mut iter = list.iter();
+            assert_eq!(iter.len(), 5);
+            assert_eq!(iter.next(), Some(&1));
+            assert_eq!(iter.next_back(), Some(&5));
+            assert_eq!(iter.next(), Some(&2));
+            assert_eq!(iter.next_back(), Some(&4));
+            assert_eq!(iter.next(), Some(&3));
+            // 前後が出会ったら終了する
+            assert_eq!(iter.next_back(), None);
+            assert_eq!(iter.next(), None);
+
+            for x in list.iter_mut().rev().take(2) {
+                *x *= 100;
+            }
+            assert_eq!(to_vec(&list), vec![1, 2, 3, 400, 500]);
+
+            let owned: Vec<i32> = list.into_iter().rev().collect();
+            assert_eq!(owned, vec![500, 400, 3, 2, 1]);
+        }
+
+        #[test]
+        fn test_cursor_movement() {
+            let mut list: DoublyLinkedList<i32> = (0..3).collect();
+            let mut cursor = list.cursor_front_mut();
+            assert_eq!(cursor.index(), Some(0));
+            assert_eq!(cursor.peek_prev(), None);
+            assert_eq!(cursor.peek_next(), Some(&mut 1));
+
+            cursor.move_next();
+            cursor.move_next();
+            assert_eq!(cursor.current(), Some(&mut 2));
+            assert_eq!(cursor.index(), Some(2));
+
+            // 末尾の次は ghost、その次は先頭に戻る
+            cursor.move_next();
+            assert_eq!(cursor.current(), None);
+            assert_eq!(cursor.index(), None);
+            assert_eq!(cursor.peek_next(), Some(&mut 0));
+            assert_eq!(cursor.peek_prev(), Some(&mut 2));
+            cursor.move_next();
+            assert_eq!(cursor.current(), Some(&mut 0));
+
+            // 先頭の前は ghost、その前は末尾
+            cursor.move_prev();
+            assert_eq!(cursor.current(), None);
+            cursor.move_prev();
+            assert_eq!(cursor.current(), Some(&mut 2));
+            assert_eq!(cursor.index(), Some(2));
+
+            let mut empty: DoublyLinkedList<i32> = DoublyLinkedList::new();
+            let mut cursor = empty.cursor_back_mut();
+            assert_eq!(cursor.current(), None);
+            cursor.move_next();
+            cursor.move_prev();
+            assert_eq!(cursor.current(), None);
+        }
+
+        #[test]
+        fn test_cursor_insert() {
+            let mut list: DoublyLinkedList<i32> = vec![1, 3].into_iter().collect();
+            let mut cursor = list.cursor_front_mut();
+            cursor.insert_after(2);
+            assert_eq!(cursor.index(), Some(0));
+            cursor.insert_before(0);
+            assert_eq!(cursor.index(), Some(1));
+            assert_eq!(cursor.current(), Some(&mut 1));
+
+            // ghost では insert_before が末尾、insert_after が先頭への追加になる
+            cursor.move_prev();
+            cursor.move_prev();
+            assert_eq!(cursor.index(), None);
+            cursor.insert_before(4);
+            cursor.insert_after(-1);
+            cursor.move_next();
+            assert_eq!(cursor.index(), Some(0));
+            assert_eq!(cursor.current(), Some(&mut -1));
+
+            assert_links(&list);
+            assert_eq!(to_vec(&list), vec![-1, 0, 1, 2, 3, 4]);
+        }
+
+        #[test]
+        fn test_cursor_remove_current() {
+            let mut list: DoublyLinkedList<i32> = (0..5).collect();
+            let mut cursor = list.cursor_front_mut();
+            cursor.move_next();
+            assert_eq!(cursor.remove_current(), Some(1));
+            // 削除後は次の要素を指す
+            assert_eq!(cursor.current(), Some(&mut 2));
+            assert_eq!(cursor.index(), Some(1));
+
+            cursor.move_prev();
+            assert_eq!(cursor.remove_current(), Some(0));
+            assert_eq!(cursor.current(), Some(&mut 2));
+            assert_eq!(cursor.index(), Some(0));
+
+            let mut cursor = list.cursor_back_mut();
+            assert_eq!(cursor.remove_current(), Some(4));
+            assert_eq!(cursor.current(), None);
+            assert_eq!(cursor.remove_current(), None);
+            cursor.move_prev();
+            assert_eq!(cursor.current(), Some(&mut 3));
+
+            assert_links(&list);
+            assert_eq!(to_vec(&list), vec![2, 3]);
+        }
+
+        #[test]
+        fn test_cursor_split_after() {
+            let mut list: DoublyLinkedList<i32> = (0..6).collect();
+            let mut cursor = list.cursor_front_mut();
+            cursor.move_next();
+            let tail = cursor.split_after();
+            assert_eq!(cursor.index(), Some(1));
+            assert_eq!(cursor.peek_next(), None);
+
+            assert_links(&list);
+            assert_links(&tail);
+            assert_eq!(to_vec(&list), vec![0, 1]);
+            assert_eq!(to_vec(&tail), vec![2, 3, 4, 5]);
+
+            // 末尾で分割すると空のリスト、ghost で分割するとリスト全体を返す
+            let mut cursor = list.cursor_back_mut();
+            assert!(cursor.split_after().is_empty());
+            cursor.move_next();
+            let all = cursor.split_after();
+            assert_eq!(to_vec(&all), vec![0, 1]);
+            assert!(list.is_empty());
+        }
+
+        #[test]
+        fn test_cursor_splice() {
+            let mut list: DoublyLinkedList<i32> = vec![1, 5].into_iter().collect();
+            let mut cursor = list.cursor_front_mut();
+            cursor.splice_after((2..5).collect());
+            assert_eq!(cursor.index(), Some(0));
+            cursor.splice_before(vec![-1, 0].into_iter().collect());
+            assert_eq!(cursor.index(), Some(2));
+            assert_eq!(cursor.current(), Some(&mut 1));
+
+            // 空のリストを差し込んでも何も変わらない
+            cursor.splice_after(DoublyLinkedList::new());
+
+            // ghost では splice_before が末尾、splice_after が先頭への連結になる
+            let mut cursor = list.cursor_back_mut();
+            cursor.move_next();
+            cursor.splice_before(vec![6, 7].into_iter().collect());
+            cursor.splice_after(vec![-2].into_iter().collect());
+            assert_eq!(cursor.index(), None);
+            cursor.move_prev();
+            assert_eq!(cursor.index(), Some(9));
+
+            assert_links(&list);
+            assert_eq!(to_vec(&list), (-2..=7).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn test_lru_move_to_front() {
+            // LRU キャッシュの「使った要素を先頭へ移す」操作
+            let mut lru: DoublyLinkedList<&str> = vec!["a", "b", "c", "d"].into_iter().collect();
+
+            let mut cursor = lru.cursor_front_mut();
+            while cursor.current().is_some_and(|key| *key != "c") {
+                cursor.move_next();
+            }
+            let key = cursor.remove_current().unwrap();
+            lru.push_front(key);
+
+            // 最も古い要素は末尾から取り出す
+            assert_eq!(to_vec(&lru), vec!["c", "a", "b", "d"]);
+            assert_eq!(lru.pop_back(), Some("d"));
+        }
+
+        #[test]
+        fn test_clone_eq_debug() {
+            let list: DoublyLinkedList<i32> = (1..=3).collect();
+            let mut cloned = list.clone();
+            assert_eq!(list, cloned);
+            cloned.push_front(0);
+            assert_ne!(list, cloned);
+            assert_eq!(format!("{:?}", list), "[1, 2, 3]");
+        }
+
+        #[test]
+        fn test_drop_elements() {
+            use std::rc::Rc;
+
+            let marker = Rc::new(());
+            let mut list = DoublyLinkedList::new();
+            for _ in 0..10 {
+                list.push_back(Rc::clone(&marker));
+            }
+            let tail = list.cursor_front_mut().split_after();
+            let mut iter = tail.into_iter();
+            iter.next();
+            drop(iter);
+            assert_eq!(Rc::strong_count(&marker), 2);
+            drop(list);
+            assert_eq!(Rc::strong_count(&marker), 1);
+        }
+
+        #[test]
+        fn test_drop_long_list_on_small_stack() {
+            let handle = std::thread::Builder::new()
+                .stack_size(64 * 1024)
+                .spawn(|| {
+                    let list: DoublyLinkedList<u32> = (0..1_000_000).collect();
+                    drop(list);
+                })
+                .unwrap();
+            handle.join().unwrap();
+        }
+    }
+}
+
+// ---------------------------------------------------------
+// 課題3: スタック（LIFO）
+// ---------------------------------------------------------
+
+pub mod stack {
+    use std::cmp::Reverse;
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    use crate::array_vec::array_vec::ArrayVec;
+    use crate::week1_basic_structures::linked_list::LinkedList;
+    use crate::week1_basic_structures::my_vec::{Allocator, GrowthSource, MyVec};
+
+    /// スタックの要素を格納するコンテナ
+    ///
+    /// トップへの追加・削除・参照ができれば何でもよい。
+    /// 配列系のコンテナは末尾を、連結リストは先頭をトップとして使う。
+    pub trait StackStorage<T> {
+        /// トップに追加（容量がいっぱいなら値をそのまま返す）
+        fn try_push(&mut self, item: T) -> Result<(), T>;
+
+        /// トップを削除して返す
+        fn pop(&mut self) -> Option<T>;
+
+        /// トップを参照
+        fn top(&self) -> Option<&T>;
+
+        /// トップを可変参照
+        fn top_mut(&mut self) -> Option<&mut T>;
+
+        /// 要素数
+        fn len(&self) -> usize;
+
+        /// 空かどうか
+        fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+    }
+
+    impl<T> StackStorage<T> for Vec<T> {
+        fn try_push(&mut self, item: T) -> Result<(), T> {
+            self.push(item);
+            Ok(())
+        }
+
+        fn pop(&mut self) -> Option<T> {
+            Vec::pop(self)
+        }
+
+        fn top(&self) -> Option<&T> {
+            self.last()
+        }
+
+        fn top_mut(&mut self) -> Option<&mut T> {
+            self.last_mut()
+        }
+
+        fn len(&self) -> usize {
+            Vec::len(self)
+        }
+    }
+
+    impl<T, A: Allocator, G: GrowthSource> StackStorage<T> for MyVec<T, A, G> {
+        fn try_push(&mut self, item: T) -> Result<(), T> {
+            // メモリ確保に失敗した場合も、値を返して呼び出し側に任せる
+            MyVec::try_push(self, item).map_err(|(item, _)| item)
+        }
+
+        fn pop(&mut self) -> Option<T> {
+            MyVec::pop(self)
+        }
+
+        fn top(&self) -> Option<&T> {
+            self.as_slice().last()
+        }
+
+        fn top_mut(&mut self) -> Option<&mut T> {
+            self.as_mut_slice().last_mut()
+        }
+
+        fn len(&self) -> usize {
+            MyVec::len(self)
+        }
+    }
+
+    impl<T, const N: usize> StackStorage<T> for ArrayVec<T, N> {
+        fn try_push(&mut self, item: T) -> Result<(), T> {
+            ArrayVec::try_push(self, item).map_err(|e| e.element())
+        }
+
+        fn pop(&mut self) -> Option<T> {
+            ArrayVec::pop(self)
+        }
+
+        fn top(&self) -> Option<&T> {
+            self.as_slice().last()
+        }
+
+        fn top_mut(&mut self) -> Option<&mut T> {
+            self.as_mut_slice().last_mut()
+        }
+
+        fn len(&self) -> usize {
+            ArrayVec::len(self)
+        }
+    }
+
+    impl<T> StackStorage<T> for LinkedList<T> {
+        fn try_push(&mut self, item: T) -> Result<(), T> {
+            self.push_front(item);
+            Ok(())
+        }
+
+        fn pop(&mut self) -> Option<T> {
+            self.pop_front()
+        }
+
+        fn top(&self) -> Option<&T> {
+            self.peek()
+        }
+
+        fn top_mut(&mut self) -> Option<&mut T> {
+            self.iter_mut().next()
+        }
+
+        fn len(&self) -> usize {
+            LinkedList::len(self)
+        }
+    }
+
+    /// スタックの実装
+    ///
+    /// 要素の格納先は `StackStorage` を実装したコンテナから選べる（既定は Vec）。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut stack: Stack<i32, ArrayVec<i32, 2>> = Stack::with_storage(ArrayVec::new());
+    /// stack.push(1);
+    /// stack.push(2);
+    /// assert_eq!(stack.try_push(3), Err(3));
+    /// assert_eq!(stack.pop(), Some(2));
+    /// ```
+    pub struct Stack<T, S: StackStorage<T> = Vec<T>> {
+        items: S,
+        _marker: PhantomData<T>,
+    }
+
+    impl<T> Stack<T> {
+        /// 新しい空のスタックを作成（Vec を使う）
+        pub fn new() -> Self {
+            Self::with_storage(Vec::new())
+        }
+    }
+
+    impl<T, S: StackStorage<T>> Stack<T, S> {
+        /// 指定したコンテナを格納先にしてスタックを作成
+        ///
+        /// コンテナに入っている要素はそのまま残り、トップ側から取り出される。
+        pub fn with_storage(storage: S) -> Self {
+            Stack {
+                items: storage,
+                _marker: PhantomData,
+            }
+        }
+
+        /// 要素をプッシュ
+        ///
+        /// # Panics
+        /// 格納先がいっぱいの場合
+        pub fn push(&mut self, item: T) {
+            if self.try_push(item).is_err() {
+                panic!("Stack::push: storage is full");
+            }
+        }
+
+        /// 要素をプッシュ（格納先がいっぱいなら値をそのまま返す）
+        pub fn try_push(&mut self, item: T) -> Result<(), T> {
+            self.items.try_push(item)
+        }
+
+        /// 要素をポップ
+        pub fn pop(&mut self) -> Option<T> {
+            self.items.pop()
+        }
+
+        /// トップの要素を参照
+        pub fn peek(&self) -> Option<&T> {
+            self.items.top()
+        }
+
+        /// トップの要素を可変参照
+        pub fn peek_mut(&mut self) -> Option<&mut T> {
+            self.items.top_mut()
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// 要素数
+        pub fn len(&self) -> usize {
+            self.items.len()
+        }
+
+        /// 格納先のコンテナを取り出す
+        pub fn into_storage(self) -> S {
+            self.items
+        }
+    }
+
+    impl<T, S: StackStorage<T> + Default> Default for Stack<T, S> {
+        fn default() -> Self {
+            Self::with_storage(S::default())
+        }
+    }
+
+    /// 容量いっぱいの `BoundedStack` に push したときの振る舞い
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum OverflowPolicy {
+        /// 新しい要素を追加せずに返す
+        Reject,
+        /// 最も古い（底の）要素を捨てて追加する
+        DropOldest,
+        /// パニックする
+        Panic,
+    }
+
+    /// 要素数に上限のあるスタック
+    ///
+    /// 編集履歴（Undo）のように「直近 N 件だけ残したい」用途向け。
+    /// `OverflowPolicy::DropOldest` では底の要素を O(1) で捨てる必要があるため、
+    /// 格納先は両端から出し入れできる VecDeque に固定している。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut history = BoundedStack::new(2, OverflowPolicy::DropOldest);
+    /// history.push("a");
+    /// history.push("b");
+    /// assert_eq!(history.push("c"), Ok(Some("a")));
+    /// assert_eq!(history.pop(), Some("c"));
+    /// assert_eq!(history.pop(), Some("b"));
+    /// assert_eq!(history.pop(), None);
+    /// ```
+    pub struct BoundedStack<T> {
+        items: VecDeque<T>, // 先頭が底、末尾がトップ
+        capacity: usize,
+        policy: OverflowPolicy,
+    }
+
+    impl<T> BoundedStack<T> {
+        /// 容量と、容量を超えたときの振る舞いを指定して作成
+        ///
+        /// # Panics
+        /// capacity が 0 の場合
+        pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+            assert!(capacity > 0, "BoundedStack capacity must be non-zero");
+            BoundedStack {
+                items: VecDeque::with_capacity(capacity),
+                capacity,
+                policy,
+            }
+        }
+
+        /// 要素をプッシュ
+        ///
+        /// 容量に空きがあれば `Ok(None)`。いっぱいの場合はポリシーに従い、
+        /// `Reject` なら `Err(item)`、`DropOldest` なら捨てた要素を `Ok(Some(oldest))` で返す。
+        ///
+        /// # Panics
+        /// 容量がいっぱいで、ポリシーが `Panic` の場合
+        pub fn push(&mut self, item: T) -> Result<Option<T>, T> {
+            let mut dropped = None;
+            if self.is_full() {
+                match self.policy {
+                    OverflowPolicy::Reject => return Err(item),
+                    OverflowPolicy::DropOldest => dropped = self.items.pop_front(),
+                    OverflowPolicy::Panic => {
+                        panic!("BoundedStack::push: capacity {} exceeded", self.capacity)
+                    }
+                }
+            }
+            self.items.push_back(item);
+            Ok(dropped)
+        }
+
+        /// 要素をポップ
+        pub fn pop(&mut self) -> Option<T> {
+            self.items.pop_back()
+        }
+
+        /// トップの要素を参照
+        pub fn peek(&self) -> Option<&T> {
+            self.items.back()
+        }
+
+        /// トップの要素を可変参照
+        pub fn peek_mut(&mut self) -> Option<&mut T> {
+            self.items.back_mut()
+        }
+
+        /// すべての要素を削除
+        pub fn clear(&mut self) {
+            self.items.clear();
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.items.is_empty()
+        }
+
+        /// 容量いっぱいかどうか
+        pub fn is_full(&self) -> bool {
+            self.items.len() == self.capacity
+        }
+
+        /// 要素数
+        pub fn len(&self) -> usize {
+            self.items.len()
+        }
+
+        /// 容量（保持できる要素数の上限）
+        pub fn capacity(&self) -> usize {
+            self.capacity
+        }
+
+        /// 容量を超えたときの振る舞い
+        pub fn policy(&self) -> OverflowPolicy {
+            self.policy
+        }
+    }
+
+    /// 最小値を O(1) で取り出せるスタック
+    ///
+    /// 要素のスタックとは別に「その時点の最小値の位置」を積む補助スタックを持つ。
+    /// 新しい要素が現在の最小値以下ならその位置を補助スタックにも積み、
+    /// その要素を pop するときに補助スタックからも取り除く。
+    ///
+    /// # Examples
+    /// ```
+    /// let mut stack = MinStack::new();
+    /// stack.push(3);
+    /// stack.push(1);
+    /// stack.push(2);
+    /// assert_eq!(stack.min(), Some(&1));
+    /// stack.pop();
+    /// stack.pop();
+    /// assert_eq!(stack.min(), Some(&3));
+    /// ```
+    pub struct MinStack<T: Ord> {
+        items: Stack<T>,
+        mins: Stack<usize>, // 最小値が更新された要素の位置（トップが現在の最小値）
+    }
+
+    impl<T: Ord> MinStack<T> {
+        /// 新しい空のスタックを作成
+        pub fn new() -> Self {
+            MinStack {
+                items: Stack::new(),
+                mins: Stack::new(),
+            }
+        }
+
+        /// 要素をプッシュ（O(1)）
+        pub fn push(&mut self, item: T) {
+            // 同じ値も積むので、重複した最小値を 1 つ pop しても最小値は残る
+            if self.min().is_none_or(|min| item <= *min) {
+                self.mins.push(self.items.len());
+            }
+            self.items.push(item);
+        }
+
+        /// 要素をポップ（O(1)）
+        pub fn pop(&mut self) -> Option<T> {
+            let item = self.items.pop()?;
+            if self.mins.peek() == Some(&self.items.len()) {
+                self.mins.pop();
+            }
+            Some(item)
+        }
+
+        /// トップの要素を参照
+        pub fn peek(&self) -> Option<&T> {
+            self.items.peek()
+        }
+
+        /// 最小の要素を参照（O(1)）
+        pub fn min(&self) -> Option<&T> {
+            self.mins.peek().map(|&index| &self.items.items[index])
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.items.is_empty()
+        }
+
+        /// 要素数
+        pub fn len(&self) -> usize {
+            self.items.len()
+        }
+    }
+
+    impl<T: Ord> Default for MinStack<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// 最大値を O(1) で取り出せるスタック
+    ///
+    /// 大小を逆にした `MinStack<Reverse<T>>` で実装している。
+    pub struct MaxStack<T: Ord> {
+        inner: MinStack<Reverse<T>>,
+    }
+
+    impl<T: Ord> MaxStack<T> {
+        /// 新しい空のスタックを作成
+        pub fn new() -> Self {
+            MaxStack {
+                inner: MinStack::new(),
+            }
+        }
+
+        /// 要素をプッシュ（O(1)）
+        pub fn push(&mut self, item: T) {
+            self.inner.push(Reverse(item));
+        }
+
+        /// 要素をポップ（O(1)）
+        pub fn pop(&mut self) -> Option<T> {
+            self.inner.pop().map(|Reverse(item)| item)
+        }
+
+        /// トップの要素を参照
+        pub fn peek(&self) -> Option<&T> {
+            self.inner.peek().map(|Reverse(item)| item)
+        }
+
+        /// 最大の要素を参照（O(1)）
+        pub fn max(&self) -> Option<&T> {
+            self.inner.min().map(|Reverse(item)| item)
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.inner.is_empty()
+        }
+
+        /// 要素数
+        pub fn len(&self) -> usize {
+            self.inner.len()
+        }
+    }
+
+    impl<T: Ord> Default for MaxStack<T> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// 単調スタックで保つ順序（底からトップに向かって）
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Order {
+        Increasing,
+        NonDecreasing,
+        Decreasing,
+        NonIncreasing,
+    }
+
+    /// 底からトップまで値が単調に並ぶように保つスタック
+    ///
+    /// 新しい値を積む前に、順序を崩す要素を `pop_if` で取り除く。
+    /// 取り除かれる要素にとっては、新しい値が「次に現れた、より大きい（小さい）値」になる。
+    /// 各要素は 1 回ずつしか push / pop されないので、列全体の処理は O(n)。
+    ///
+    /// 要素は (元の列でのインデックス, 値) の組で持つ。
+    ///
+    /// # Examples
+    /// ```
+    /// // 各要素の次に現れる、より大きい要素の位置
+    /// assert_eq!(
+    ///     next_greater_element(&[2, 1, 3]),
+    ///     vec![Some(2), Some(2), None]
+    /// );
+    /// ```
+    pub struct MonotonicStack<T: Ord> {
+        items: Stack<(usize, T)>,
+        order: Order,
+    }
+
+    impl<T: Ord> MonotonicStack<T> {
+        /// 狭義単調増加（トップが最大）のスタック。新しい値以上の要素を取り除く
+        pub fn increasing() -> Self {
+            Self::with_order(Order::Increasing)
+        }
+
+        /// 広義単調増加のスタック。新しい値より大きい要素を取り除く
+        pub fn non_decreasing() -> Self {
+            Self::with_order(Order::NonDecreasing)
+        }
+
+        /// 狭義単調減少（トップが最小）のスタック。新しい値以下の要素を取り除く
+        pub fn decreasing() -> Self {
+            Self::with_order(Order::Decreasing)
+        }
+
+        /// 広義単調減少のスタック。新しい値より小さい要素を取り除く
+        pub fn non_increasing() -> Self {
+            Self::with_order(Order::NonIncreasing)
+        }
+
+        fn with_order(order: Order) -> Self {
+            MonotonicStack {
+                items: Stack::new(),
+                order,
+            }
+        }
+
+        /// value を積むと順序が崩れる場合、トップを取り除いて返す
+        ///
+        /// `while let Some(top) = stack.pop_if(&value) { ... }` のように繰り返し呼ぶ。
+        pub fn pop_if(&mut self, value: &T) -> Option<(usize, T)> {
+            let (_, top) = self.items.peek()?;
+            if self.keeps(top, value) {
+                None
+            } else {
+                self.items.pop()
+            }
+        }
+
+        /// 要素をプッシュ
+        ///
+        /// # Panics
+        /// 順序が崩れる場合（先に `pop_if` で取り除いておくこと）
+        pub fn push(&mut self, index: usize, value: T) {
+            if let Some((_, top)) = self.items.peek() {
+                assert!(
+                    self.keeps(top, &value),
+                    "push would break the monotonic order"
+                );
+            }
+            self.items.push((index, value));
+        }
+
+        /// トップの要素を参照
+        pub fn peek(&self) -> Option<&(usize, T)> {
+            self.items.peek()
+        }
+
+        /// 空かどうか
+        pub fn is_empty(&self) -> bool {
+            self.items.is_empty()
+        }
+
+        /// 要素数
+        pub fn len(&self) -> usize {
+            self.items.len()
+        }
+
+        /// top の上に value を積んでも順序が保たれるか
+        fn keeps(&self, top: &T, value: &T) -> bool {
+            match self.order {
+                Order::Increasing => top < value,
+                Order::NonDecreasing => top <= value,
+                Order::Decreasing => top > value,
+                Order::NonIncreasing => top >= value,
+            }
+        }
+    }
+
+    /// 各要素について、右側で最初に現れるより大きい要素の位置（なければ None）
+    ///
+    /// 広義単調減少のスタックを使い、新しい値より小さい要素を取り除くときに答えが決まる。
+    pub fn next_greater_element<T: Ord>(values: &[T]) -> Vec<Option<usize>> {
+        let mut result = vec![None; values.len()];
+        let mut stack = MonotonicStack::non_increasing();
+        for (i, value) in values.iter().enumerate() {
+            while let Some((j, _)) = stack.pop_if(&value) {
+                result[j] = Some(i);
+            }
+            stack.push(i, value);
+        }
+        result
+    }
+
+    /// 株価のスパン: 各日について、その日以前に連続して株価がその日以下だった日数（その日を含む）
+    ///
+    /// 狭義単調減少のスタックを使い、その日以下の日を取り除いた後のトップが
+    /// 「直前のより高い日」になる。
+    pub fn stock_span<T: Ord>(prices: &[T]) -> Vec<usize> {
+        let mut stack = MonotonicStack::decreasing();
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| {
+                while stack.pop_if(&price).is_some() {}
+                let span = match stack.peek() {
+                    Some(&(previous_higher, _)) => i - previous_higher,
+                    None => i + 1,
+                };
+                stack.push(i, price);
+                span
+            })
+            .collect()
+    }
+
+    /// ヒストグラム中の最大の長方形の面積
+    ///
+    /// 広義単調増加のスタックを使う。ある棒が取り除かれるとき、右端は新しい棒の手前、
+    /// 左端は取り除いた後のトップの次なので、その棒の高さで作れる最大の幅が決まる。
+    /// 面積は u64 に収まらないことがある（`[u64::MAX, u64::MAX]` など）ので u128 で返す。
+    pub fn largest_rectangle(heights: &[u64]) -> u128 {
+        let mut stack = MonotonicStack::non_decreasing();
+        let mut best = 0;
+        // 最後に高さ 0 の番兵を置いて、残った棒をすべて取り除く
+        for (i, height) in heights.iter().copied().chain([0]).enumerate() {
+            while let Some((_, popped)) = stack.pop_if(&height) {
+                let left = stack.peek().map_or(0, |&(j, _)| j + 1);
+                best = best.max(popped as u128 * (i - left) as u128);
+            }
+            stack.push(i, height);
+        }
+        best
+    }
+
+    /// 入力中の位置
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Position {
+        /// 先頭からのバイトオフセット
+        pub offset: usize,
+        /// 行番号（1 始まり）
+        pub line: usize,
+        /// 行内の文字単位の列番号（1 始まり）
+        pub column: usize,
+    }
+
+    impl fmt::Display for Position {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "line {}, column {}", self.line, self.column)
+        }
+    }
+
+    /// 開き括弧とその位置
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Opener {
+        pub bracket: char,
+        pub position: Position,
+    }
+
+    /// 括弧の対応が取れていない理由
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum BracketError {
+        /// 閉じ括弧が、直前の開き括弧と対応していない
+        Mismatched {
+            /// 期待していた閉じ括弧
+            expected: char,
+            /// 実際に現れた閉じ括弧
+            found: char,
+            /// 閉じ括弧の位置
+            position: Position,
+            /// 対応するはずだった開き括弧
+            opener: Opener,
+        },
+        /// 開き括弧がないのに閉じ括弧が現れた
+        UnexpectedCloser { found: char, position: Position },
+        /// 入力の終わりまで閉じられなかった開き括弧（開いた順）
+        Unclosed { openers: Vec<Opener> },
+    }
+
+    impl fmt::Display for BracketError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                BracketError::Mismatched {
+                    expected,
+                    found,
+                    position,
+                    opener,
+                } => write!(
+                    f,
+                    "mismatched closing bracket '{}' at {}: expected '{}' to close '{}' at {}",
+                    found, position, expected, opener.bracket, opener.position
+                ),
+                BracketError::UnexpectedCloser { found, position } => {
+                    write!(f, "unexpected closing bracket '{}' at {}", found, position)
+                }
+                BracketError::Unclosed { openers } => {
+                    write!(f, "unclosed bracket")?;
+                    if openers.len() > 1 {
+                        write!(f, "s")?;
+                    }
+                    for (i, opener) in openers.iter().enumerate() {
+                        let sep = if i == 0 { ": " } else { ", " };
+                        write!(f, "{}'{}' at {}", sep, opener.bracket, opener.position)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for BracketError {}
+
+    /// `BracketChecker` が見つけた開き記号とその位置
+    ///
+    /// 括弧のほか、閉じられていない文字列やコメントの開始記号も表す。
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct OpenToken {
+        /// 開き記号（`begin` のような複数文字の場合もある）
+        pub token: String,
+        pub position: Position,
+    }
+
+    /// `BracketChecker` が見つけた問題
+    ///
+    /// `BracketError` と違い、括弧は複数文字の場合もあるので文字列で持つ。
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum CheckError {
+        /// 閉じ括弧が、直前の開き括弧と対応していない
+        Mismatched {
+            /// 期待していた閉じ括弧
+            expected: String,
+            /// 実際に現れた閉じ括弧
+            found: String,
+            /// 閉じ括弧の位置
+            position: Position,
+            /// 対応するはずだった開き括弧
+            opener: OpenToken,
+        },
+        /// 開き括弧がないのに閉じ括弧が現れた
+        UnexpectedCloser { found: String, position: Position },
+        /// 入力の終わりまで閉じられなかった開き括弧（開いた順）
+        Unclosed { openers: Vec<OpenToken> },
+        /// 文字列リテラルが閉じられていない（opener は開始の区切り文字）
+        UnterminatedString { opener: OpenToken },
+        /// ブロックコメントが閉じられていない（opener は開始の記号）
+        UnterminatedComment { opener: OpenToken },
+    }
+
+    impl fmt::Display for CheckError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                CheckError::Mismatched {
+                    expected,
+                    found,
+                    position,
+                    opener,
+                } => write!(
+                    f,
+                    "mismatched closing bracket '{}' at {}: expected '{}' to close '{}' at {}",
+                    found, position, expected, opener.token, opener.position
+                ),
+                CheckError::UnexpectedCloser { found, position } => {
+                    write!(f, "unexpected closing bracket '{}' at {}", found, position)
+                }
+                CheckError::Unclosed { openers } => {
+                    write!(f, "unclosed bracket")?;
+                    if openers.len() > 1 {
+                        write!(f, "s")?;
+                    }
+                    for (i, opener) in openers.iter().enumerate() {
+                        let sep = if i == 0 { ": " } else { ", " };
+                        write!(f, "{}'{}' at {}", sep, opener.token, opener.position)?;
+                    }
+                    Ok(())
+                }
+                CheckError::UnterminatedString { opener } => write!(
+                    f,
+                    "unterminated string starting with '{}' at {}",
+                    opener.token, opener.position
+                ),
+                CheckError::UnterminatedComment { opener } => write!(
+                    f,
+                    "unterminated comment starting with '{}' at {}",
+                    opener.token, opener.position
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for CheckError {}
+
+    /// 文字列リテラルの規則
+    #[derive(Debug, Clone)]
+    struct StringRule {
+        delimiter: String,
+        escape: Option<char>,
+    }
+
+    /// ブロックコメントの規則
+    #[derive(Debug, Clone)]
+    struct BlockComment {
+        open: String,
+        close: String,
+        nested: bool,
+    }
+
+    /// 文字列やコメントの中を読み飛ばしながら括弧の対応をチェックする
+    ///
+    /// 括弧の組（`begin` / `end` のような複数文字も可）、文字列の区切り文字と
+    /// エスケープ文字、行コメント・ブロックコメントの記号を指定して作る。
+    /// 英数字で始まる（終わる）括弧は、前（後ろ）が識別子の文字でないときだけ認識するので、
+    /// `append` の中の `end` は閉じ括弧として扱わない。
+    ///
+    /// # Examples
+    /// ```
+    /// let checker = BracketChecker::rust();
+    /// assert!(checker.check(r#"let s = "(";  // ) "#).is_ok());
+    ///
+    /// let pascal = BracketChecker::new().pair("begin", "end").pair("(", ")");
+    /// assert!(pascal.check("begin x := f(1) end").is_ok());
+    /// ```
+    #[derive(Debug, Clone, Default)]
+    pub struct BracketChecker {
+        pairs: Vec<(String, String)>,
+        strings: Vec<StringRule>,
+        char_literals: Option<(char, char)>, // (引用符, エスケープ文字)
+        char_prefixes: Vec<String>,
+        line_comments: Vec<String>,
+        block_comments: Vec<BlockComment>,
+    }
+
+    impl BracketChecker {
+        /// 何も設定していないチェッカーを作成
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// `()`, `[]`, `{}` だけを数えるチェッカー（文字列やコメントは区別しない）
+        pub fn plain() -> Self {
+            Self::new().pair("(", ")").pair("[", "]").pair("{", "}")
+        }
+
+        /// Rust 用のプリセット
+        ///
+        /// 文字列、文字リテラル（ライフタイムの `'a` とは区別する）、
+        /// 行コメント、入れ子にできるブロックコメントを読み飛ばす。
+        /// raw 文字列（`r#"..."#`）には対応していない。
+        pub fn rust() -> Self {
+            Self::plain()
+                .string("\"", Some('\\'))
+                .char_literals('\'', '\\')
+                .line_comment("//")
+                .nested_block_comment("/*", "*/")
+        }
+
+        /// JSON 用のプリセット（文字列の中だけを読み飛ばす。JSON にコメントはない）
+        pub fn json() -> Self {
+            Self::new()
+                .pair("[", "]")
+                .pair("{", "}")
+                .string("\"", Some('\\'))
+        }
+
+        /// Lisp（Common Lisp）用のプリセット
+        ///
+        /// `;` の行コメントと、入れ子にできる `#| ... |#` のブロックコメント、
+        /// `#\(` のような文字リテラルを読み飛ばす。
+        pub fn lisp() -> Self {
+            Self::new()
+                .pair("(", ")")
+                .pair("[", "]")
+                .string("\"", Some('\\'))
+                .prefixed_chars("#\\")
+                .line_comment(";")
+                .nested_block_comment("#|", "|#")
+        }
+
+        /// 括弧の組を追加
+        ///
+        /// 絶対値の `|x|` のように開き括弧と閉じ括弧が同じ場合は、
+        /// スタックのトップが同じ組の開き括弧なら閉じ括弧、そうでなければ開き括弧とみなす。
+        pub fn pair(mut self, open: &str, close: &str) -> Self {
+            assert!(
+                !open.is_empty() && !close.is_empty(),
+                "brackets must not be empty"
+            );
+            self.pairs.push((open.to_string(), close.to_string()));
+            self
+        }
+
+        /// 文字列リテラルの区切り文字を追加（escape の次の文字は区切り文字として扱わない）
+        pub fn string(mut self, delimiter: &str, escape: Option<char>) -> Self {
+            assert!(!delimiter.is_empty(), "string delimiter must not be empty");
+            self.strings.push(StringRule {
+                delimiter: delimiter.to_string(),
+                escape,
+            });
+            self
+        }
+
+        /// `'x'` や `'\n'` のような文字リテラルを読み飛ばす
+        ///
+        /// 1 文字（またはエスケープシーケンス）の後に引用符が続かない場合は
+        /// 文字リテラルではない（Rust のライフタイムなど）とみなす。
+        pub fn char_literals(mut self, quote: char, escape: char) -> Self {
+            self.char_literals = Some((quote, escape));
+            self
+        }
+
+        /// `#\a` のように、prefix の直後の 1 文字を文字リテラルとして読み飛ばす
+        pub fn prefixed_chars(mut self, prefix: &str) -> Self {
+            assert!(!prefix.is_empty(), "char prefix must not be empty");
+            self.char_prefixes.push(prefix.to_string());
+            self
+        }
+
+        /// 行コメントの開始記号を追加
+        pub fn line_comment(mut self, marker: &str) -> Self {
+            assert!(!marker.is_empty(), "comment marker must not be empty");
+            self.line_comments.push(marker.to_string());
+            self
+        }
+
+        /// ブロックコメントの記号を追加
+        pub fn block_comment(self, open: &str, close: &str) -> Self {
+            self.add_block_comment(open, close, false)
+        }
+
+        /// 入れ子にできるブロックコメントの記号を追加
+        pub fn nested_block_comment(self, open: &str, close: &str) -> Self {
+            self.add_block_comment(open, close, true)
+        }
+
+        fn add_block_comment(mut self, open: &str, close: &str, nested: bool) -> Self {
+            assert!(
+                !open.is_empty() && !close.is_empty(),
+                "comment markers must not be empty"
+            );
+            self.block_comments.push(BlockComment {
+                open: open.to_string(),
+                close: close.to_string(),
+                nested,
+            });
+            self
+        }
+
+        /// 括弧の対応をチェックし、最初に見つかった問題の位置を返す
+        pub fn check(&self, s: &str) -> Result<(), CheckError> {
+            // 1. コメントと文字列は中身ごと読み飛ばす
+            // 2. 開き括弧が来たら、どの組かと位置をスタックにプッシュ
+            // 3. 閉じ括弧が来たらスタックからポップして対応を確認
+            // 4. 最後にスタックに残った開き括弧は閉じられていない
+            let mut stack: Stack<(usize, OpenToken)> = Stack::new();
+            let mut scanner = Scanner::new(s);
+
+            while !scanner.rest().is_empty() {
+                let position = scanner.position();
+                let rest = scanner.rest();
+
+                if let Some(marker) = self
+                    .line_comments
+                    .iter()
+                    .find(|m| rest.starts_with(m.as_str()))
+                {
+                    scanner.advance(marker.len());
+                    let line_len = scanner.rest().find('\n').unwrap_or(scanner.rest().len());
+                    scanner.advance(line_len);
+                    continue;
+                }
+                if let Some(comment) = self
+                    .block_comments
+                    .iter()
+                    .find(|c| rest.starts_with(c.open.as_str()))
+                {
+                    self.skip_block_comment(&mut scanner, comment)?;
+                    continue;
+                }
+                if let Some(rule) = self
+                    .strings
+                    .iter()
+                    .find(|r| rest.starts_with(r.delimiter.as_str()))
+                {
+                    self.skip_string(&mut scanner, rule)?;
+                    continue;
+                }
+                if let Some(len) = self.char_literal_len(rest) {
+                    scanner.advance(len);
+                    continue;
+                }
+
+                let top = stack.peek().map(|&(index, _)| index);
+                match self.match_bracket(&scanner, top) {
+                    Some((index, true)) => {
+                        let token = self.pairs[index].0.clone();
+                        scanner.advance(token.len());
+                        stack.push((index, OpenToken { token, position }));
+                    }
+                    Some((index, false)) => {
+                        let found = self.pairs[index].1.clone();
+                        scanner.advance(found.len());
+                        match stack.pop() {
+                            Some((open_index, opener)) => {
+                                let expected = &self.pairs[open_index].1;
+                                if *expected != found {
+                                    return Err(CheckError::Mismatched {
+                                        expected: expected.clone(),
+                                        found,
+                                        position,
+                                        opener,
+                                    });
+                                }
+                            }
+                            None => {
+                                return Err(CheckError::UnexpectedCloser { found, position });
+                            }
+                        }
+                    }
+                    None => {
+                        scanner.advance_char();
+                    }
+                }
+            }
+
+            if stack.is_empty() {
+                Ok(())
+            } else {
+                Err(CheckError::Unclosed {
+                    openers: stack.items.into_iter().map(|(_, opener)| opener).collect(),
+                })
+            }
+        }
+
+        /// 現在位置にある括弧のうち最も長いもの（組の番号, 開き括弧か）
+        ///
+        /// top はスタックのトップにある開き括弧の組の番号（開きと閉じが同じ組の判定に使う）。
+        fn match_bracket(
+            &self,
+            scanner: &Scanner<'_>,
+            top: Option<usize>,
+        ) -> Option<(usize, bool)> {
+            let mut best: Option<(usize, bool, usize)> = None;
+            for (index, (open, close)) in self.pairs.iter().enumerate() {
+                for (token, is_open) in [(open, true), (close, false)] {
+                    // 同じ記号なら、直前に開いた組を閉じるときだけ閉じ括弧として扱う
+                    if open == close && is_open == (top == Some(index)) {
+                        continue;
+                    }
+                    let longer = best.is_none_or(|(_, _, len)| token.len() > len);
+                    if longer && scanner.matches_token(token) {
+                        best = Some((index, is_open, token.len()));
+                    }
+                }
+            }
+            best.map(|(index, is_open, _)| (index, is_open))
+        }
+
+        /// ブロックコメントを閉じ記号の後ろまで読み飛ばす
+        fn skip_block_comment(
+            &self,
+            scanner: &mut Scanner<'_>,
+            comment: &BlockComment,
+        ) -> Result<(), CheckError> {
+            let opener = OpenToken {
+                token: comment.open.clone(),
+                position: scanner.position(),
+            };
+            scanner.advance(comment.open.len());
+            let mut depth = 1;
+            while depth > 0 {
+                let rest = scanner.rest();
+                if rest.is_empty() {
+                    return Err(CheckError::UnterminatedComment { opener });
+                }
+                if rest.starts_with(comment.close.as_str()) {
+                    scanner.advance(comment.close.len());
+                    depth -= 1;
+                } else if comment.nested && rest.starts_with(comment.open.as_str()) {
+                    scanner.advance(comment.open.len());
+                    depth += 1;
+                } else {
+                    scanner.advance_char();
+                }
+            }
+            Ok(())
+        }
+
+        /// 文字列リテラルを終わりの区切り文字の後ろまで読み飛ばす
+        fn skip_string(
+            &self,
+            scanner: &mut Scanner<'_>,
+            rule: &StringRule,
+        ) -> Result<(), CheckError> {
+            let opener = OpenToken {
+                token: rule.delimiter.clone(),
+                position: scanner.position(),
+            };
+            scanner.advance(rule.delimiter.len());
+            loop {
+                let rest = scanner.rest();
+                if rest.is_empty() {
+                    return Err(CheckError::UnterminatedString { opener });
+                }
+                if rest.starts_with(rule.delimiter.as_str()) {
+                    scanner.advance(rule.delimiter.len());
+                    return Ok(());
+                }
+                let c = scanner.advance_char();
+                if c.is_some() && c == rule.escape {
+                    // エスケープされた文字は区切り文字でも読み飛ばす
+                    scanner.advance_char();
+                }
+            }
+        }
+
+        /// rest が文字リテラルで始まっていれば、そのバイト数
+        fn char_literal_len(&self, rest: &str) -> Option<usize> {
+            for prefix in &self.char_prefixes {
+                if let Some(c) = rest
+                    .strip_prefix(prefix.as_str())
+                    .and_then(|r| r.chars().next())
+                {
+                    return Some(prefix.len() + c.len_utf8());
+                }
+            }
+            let (quote, escape) = self.char_literals?;
+            let body = rest.strip_prefix(quote)?;
+            let mut chars = body.chars();
+            let first = chars.next()?;
+            let body_len = if first == escape {
+                // エスケープシーケンスは同じ行の次の引用符まで（'\'' の 2 文字目は数えない）
+                let escaped = chars.next()?;
+                let after = first.len_utf8() + escaped.len_utf8();
+                let end = body[after..].find([quote, '\n'])?;
+                if !body[after + end..].starts_with(quote) {
+                    return None;
+                }
+                after + end
+            } else if first != quote && first != '\n' && chars.next() == Some(quote) {
+                first.len_utf8()
+            } else {
+                return None;
+            };
+            Some(quote.len_utf8() + body_len + quote.len_utf8())
+        }
+    }
+
+    /// 入力を先頭から読み進めながら、行と列を数える
+    struct Scanner<'a> {
+        src: &'a str,
+        offset: usize,
+        line: usize,
+        column: usize,
+    }
+
+    impl<'a> Scanner<'a> {
+        fn new(src: &'a str) -> Self {
+            Scanner {
+                src,
+                offset: 0,
+                line: 1,
+                column: 1,
+            }
+        }
+
+        fn position(&self) -> Position {
+            Position {
+                offset: self.offset,
+                line: self.line,
+                column: self.column,
+            }
+        }
+
+        /// まだ読んでいない部分
+        fn rest(&self) -> &'a str {
+            &self.src[self.offset..]
+        }
+
+        /// bytes バイト読み進める（文字の境界でなければならない）
+        fn advance(&mut self, bytes: usize) {
+            for c in self.src[self.offset..self.offset + bytes].chars() {
+                if c == '\n' {
+                    self.line += 1;
+                    self.column = 1;
+                } else {
+                    self.column += 1;
+                }
+            }
+            self.offset += bytes;
+        }
+
+        /// 1 文字読み進めて、その文字を返す
+        fn advance_char(&mut self) -> Option<char> {
+            let c = self.rest().chars().next()?;
+            self.advance(c.len_utf8());
+            Some(c)
+        }
+
+        /// 現在位置に token があるか（英数字の端では、隣が識別子の文字でないことも確認する）
+        fn matches_token(&self, token: &str) -> bool {
+            fn is_word(c: char) -> bool {
+                c.is_alphanumeric() || c == '_'
+            }
+
+            let rest = self.rest();
+            if !rest.starts_with(token) {
+                return false;
+            }
+            let starts_word = token.chars().next().is_some_and(is_word);
+            let ends_word = token.chars().next_back().is_some_and(is_word);
+            let before = self.src[..self.offset].chars().next_back();
+            let after = rest[token.len()..].chars().next();
+            // 単語の途中（append の中の end など）では認識しない
+            let inside_word = (starts_word && before.is_some_and(is_word))
+                || (ends_word && after.is_some_and(is_word));
+            !inside_word
+        }
+    }
+
+    /// 開き括弧に対応する閉じ括弧
+    fn closer_for(opener: char) -> Option<char> {
+        match opener {
+            '(' => Some(')'),
+            '[' => Some(']'),
+            '{' => Some('}'),
+            _ => None,
+        }
+    }
+
+    /// 括弧 `()`, `[]`, `{}` の対応をチェックし、最初に見つかった問題の位置を返す
+    ///
+    /// # Examples
+    /// ```
+    /// assert!(check_brackets("{[()]}").is_ok());
+    ///
+    /// let err = check_brackets("(]").unwrap_err();
+    /// assert!(matches!(err, BracketError::Mismatched { expected: ')', found: ']', .. }));
+    /// ```
+    pub fn check_brackets(s: &str) -> Result<(), BracketError> {
+        // 1. 開き括弧が来たら位置と一緒にスタックにプッシュ
+        // 2. 閉じ括弧が来たらスタックからポップして対応を確認
+        // 3. 最後にスタックに残った開き括弧は閉じられていない
+        let mut stack: Stack<Opener> = Stack::new();
+        let mut line = 1;
+        let mut column = 1;
+
+        for (offset, c) in s.char_indices() {
+            let position = Position {
+                offset,
+                line,
+                column,
+            };
+            match c {
+                '(' | '[' | '{' => stack.push(Opener {
+                    bracket: c,
+                    position,
+                }),
+                ')' | ']' | '}' => match stack.pop() {
+                    Some(opener) => {
+                        let expected = closer_for(opener.bracket).expect("only openers are pushed");
+                        if expected != c {
+                            return Err(BracketError::Mismatched {
+                                expected,
+                                found: c,
+                                position,
+                                opener,
+                            });
+                        }
+                    }
+                    None => {
+                        return Err(BracketError::UnexpectedCloser { found: c, position });
+                    }
+                },
+                _ => {}
+            }
+
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        if stack.is_empty() {
+            Ok(())
+        } else {
+            Err(BracketError::Unclosed {
+                openers: stack.items,
+            })
+        }
+    }
+
+    /// 括弧のバランスをチェックする関数（応用問題）
+    ///
+    /// 問題の位置を知りたい場合は `check_brackets` を使う。
+    ///
+    /// # Examples
+    /// ```
+    /// assert_eq!(check_balanced_parentheses("()"), true);
+    /// assert_eq!(check_balanced_parentheses("(()"), false);
+    /// assert_eq!(check_balanced_parentheses("{[()]}"), true);
+    /// ```
+    pub fn check_balanced_parentheses(s: &str) -> bool {
+        check_brackets(s).is_ok()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::week1_basic_structures::linked_list::SplitMix64;
+
         #[test]
-        fn test_drop_count() {
-            use std::sync::atomic::{AtomicUsize, Ordering};
-            use std::sync::Arc;
+        fn test_stack_operations() {
+            let mut stack = Stack::new();
+            stack.push(1);
+            stack.push(2);
+            stack.push(3);
 
-            // Dropが何回呼ばれたかカウントする型
-            struct DropCounter {
-                count: Arc<AtomicUsize>,
+            assert_eq!(stack.peek(), Some(&3));
+            assert_eq!(stack.pop(), Some(3));
+            assert_eq!(stack.pop(), Some(2));
+            assert_eq!(stack.len(), 1);
+        }
+
+        #[test]
+        fn test_balanced_parentheses() {
+            assert!(check_balanced_parentheses("()"));
+            assert!(check_balanced_parentheses("()[]{}"));
+            assert!(check_balanced_parentheses("{[()]}"));
+            assert!(!check_balanced_parentheses("(()"));
+            assert!(!check_balanced_parentheses("({)}"));
+        }
+
+        fn pos(offset: usize, line: usize, column: usize) -> Position {
+            Position {
+                offset,
+                line,
+                column,
             }
+        }
 
-            impl Drop for DropCounter {
-                fn drop(&mut self) {
-                    self.count.fetch_add(1, Ordering::SeqCst);
+        #[test]
+        fn test_check_brackets_ok() {
+            assert_eq!(check_brackets(""), Ok(()));
+            assert_eq!(check_brackets("fn main() { let a = [1, 2]; }"), Ok(()));
+            assert_eq!(check_brackets("{\n  \"key\": [1, (2)]\n}"), Ok(()));
+        }
+
+        #[test]
+        fn test_check_brackets_mismatched() {
+            let err = check_brackets("{\n  [1, 2)\n}").unwrap_err();
+            assert_eq!(
+                err,
+                BracketError::Mismatched {
+                    expected: ']',
+                    found: ')',
+                    position: pos(9, 2, 8),
+                    opener: Opener {
+                        bracket: '[',
+                        position: pos(4, 2, 3),
+                    },
                 }
+            );
+            assert_eq!(
+                err.to_string(),
+                "mismatched closing bracket ')' at line 2, column 8: \
+                 expected ']' to close '[' at line 2, column 3"
+            );
+        }
+
+        #[test]
+        fn test_check_brackets_unexpected_closer() {
+            let err = check_brackets("a)").unwrap_err();
+            assert_eq!(
+                err,
+                BracketError::UnexpectedCloser {
+                    found: ')',
+                    position: pos(1, 1, 2),
+                }
+            );
+            assert_eq!(
+                err.to_string(),
+                "unexpected closing bracket ')' at line 1, column 2"
+            );
+        }
+
+        #[test]
+        fn test_check_brackets_unclosed() {
+            let err = check_brackets("(\n[{}\n").unwrap_err();
+            assert_eq!(
+                err,
+                BracketError::Unclosed {
+                    openers: vec![
+                        Opener {
+                            bracket: '(',
+                            position: pos(0, 1, 1),
+                        },
+                        Opener {
+                            bracket: '[',
+                            position: pos(2, 2, 1),
+                        },
+                    ],
+                }
+            );
+            assert_eq!(
+                err.to_string(),
+                "unclosed brackets: '(' at line 1, column 1, '[' at line 2, column 1"
+            );
+        }
+
+        #[test]
+        fn test_check_brackets_multibyte_position() {
+            // 列は文字単位、オフセットはバイト単位で数える
+            let err = check_brackets("あい)").unwrap_err();
+            assert_eq!(
+                err,
+                BracketError::UnexpectedCloser {
+                    found: ')',
+                    position: pos(6, 1, 3),
+                }
+            );
+        }
+
+        fn opener(token: &str, position: Position) -> OpenToken {
+            OpenToken {
+                token: token.to_string(),
+                position,
             }
+        }
 
-            let drop_count = Arc::new(AtomicUsize::new(0));
+        #[test]
+        fn test_checker_rust_skips_strings_and_comments() {
+            let checker = BracketChecker::rust();
+            assert_eq!(checker.check(r#"let s = "(";"#), Ok(()));
+            assert_eq!(checker.check(r#"let s = "\")"; f(s)"#), Ok(()));
+            assert_eq!(checker.check("f(x) // ) ] }\ng()"), Ok(()));
+            assert_eq!(checker.check("/* ( /* ] */ { */ fn f() {}"), Ok(()));
+            assert_eq!(
+                checker.check("let c = '('; let d = '\\''; let e = '\\u{28}';"),
+                Ok(())
+            );
+            // ライフタイムは文字リテラルとして扱わない
+            assert_eq!(
+                checker.check("fn f<'a>(x: &'a [u8]) -> &'a u8 { &x[0] }"),
+                Ok(())
+            );
 
-            {
-                let mut vec = MyVec::new();
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
-            } // ここで5つのDropCounterがdropされるはず
+            // 素朴なチェックでは文字列の中の括弧も数えてしまう
+            assert!(check_brackets(r#"let s = "(";"#).is_err());
+        }
 
-            // 5回dropが呼ばれたか確認
-            assert_eq!(drop_count.load(Ordering::SeqCst), 5);
+        #[test]
+        fn test_checker_rust_reports_real_errors() {
+            let checker = BracketChecker::rust();
+            let err = checker
+                .check("fn f() {\n    let s = \"}\";\n    g(]\n}")
+                .unwrap_err();
+            assert_eq!(
+                err,
+                CheckError::Mismatched {
+                    expected: ")".to_string(),
+                    found: "]".to_string(),
+                    position: pos(32, 3, 7),
+                    opener: opener("(", pos(31, 3, 6)),
+                }
+            );
+
+            assert_eq!(
+                checker.check("f(\"abc)"),
+                Err(CheckError::UnterminatedString {
+                    opener: opener("\"", pos(2, 1, 3)),
+                })
+            );
+            let err = checker.check("x /* /* */ (").unwrap_err();
+            assert_eq!(
+                err,
+                CheckError::UnterminatedComment {
+                    opener: opener("/*", pos(2, 1, 3)),
+                }
+            );
+            assert_eq!(
+                err.to_string(),
+                "unterminated comment starting with '/*' at line 1, column 3"
+            );
         }
 
         #[test]
-        fn test_drop_after_pop() {
-            use std::sync::atomic::{AtomicUsize, Ordering};
-            use std::sync::Arc;
+        fn test_checker_json() {
+            let checker = BracketChecker::json();
+            assert_eq!(checker.check(r#"{"a": "[", "b": ["\"}", 1]}"#), Ok(()));
+            // JSON では丸括弧は括弧として扱わない
+            assert_eq!(checker.check(r#"{"a": (}"#), Ok(()));
+            assert_eq!(
+                checker.check(r#"{"a": [1, 2}"#),
+                Err(CheckError::Mismatched {
+                    expected: "]".to_string(),
+                    found: "}".to_string(),
+                    position: pos(11, 1, 12),
+                    opener: opener("[", pos(6, 1, 7)),
+                })
+            );
+        }
 
-            struct DropCounter {
-                count: Arc<AtomicUsize>,
+        #[test]
+        fn test_checker_lisp() {
+            let checker = BracketChecker::lisp();
+            let source = "(defun f (x) ; (unbalanced\n  #| ( #| ) |# |#\n  (format t \"(~a\" x))";
+            assert_eq!(checker.check(source), Ok(()));
+            assert_eq!(
+                checker.check("(car '(1 2)"),
+                Err(CheckError::Unclosed {
+                    openers: vec![opener("(", pos(0, 1, 1))],
+                })
+            );
+
+            // #\( や #\; は文字なので、括弧やコメントとして扱わない
+            assert_eq!(checker.check("(char= c #\\()"), Ok(()));
+            assert_eq!(checker.check("(list #\\) #\\; #\\\")"), Ok(()));
+            assert_eq!(
+                checker.check("(list #\\( #\\;)\n)"),
+                Err(CheckError::UnexpectedCloser {
+                    found: ")".to_string(),
+                    position: pos(15, 2, 1),
+                })
+            );
+        }
+
+        #[test]
+        fn test_checker_multi_char_pairs() {
+            let checker = BracketChecker::new()
+                .pair("begin", "end")
+                .pair("(", ")")
+                .string("'", None)
+                .block_comment("{", "}");
+
+            let program = "begin\n  append(x); { end } s := 'end';\n  begin f(1) end\nend";
+            assert_eq!(checker.check(program), Ok(()));
+
+            let err = checker.check("begin f(1 end").unwrap_err();
+            assert_eq!(
+                err,
+                CheckError::Mismatched {
+                    expected: ")".to_string(),
+                    found: "end".to_string(),
+                    position: pos(10, 1, 11),
+                    opener: opener("(", pos(7, 1, 8)),
+                }
+            );
+            assert_eq!(
+                err.to_string(),
+                "mismatched closing bracket 'end' at line 1, column 11: \
+                 expected ')' to close '(' at line 1, column 8"
+            );
+
+            // 入れ子にしないブロックコメントは最初の閉じ記号で終わる
+            assert_eq!(
+                checker.check("{ { ( } )"),
+                Err(CheckError::UnexpectedCloser {
+                    found: ")".to_string(),
+                    position: pos(8, 1, 9),
+                })
+            );
+        }
+
+        #[test]
+        fn test_checker_same_open_and_close() {
+            let checker = BracketChecker::plain().pair("|", "|");
+            assert_eq!(checker.check("|x| + (|y| * |[z]|)"), Ok(()));
+            assert_eq!(
+                checker.check("|x| + |y"),
+                Err(CheckError::Unclosed {
+                    openers: vec![opener("|", pos(6, 1, 7))],
+                })
+            );
+            // `(` の中の `|` は外側の `|` を閉じず、新しく開く
+            assert_eq!(
+                checker.check("|(x|)"),
+                Err(CheckError::Mismatched {
+                    expected: "|".to_string(),
+                    found: ")".to_string(),
+                    position: pos(4, 1, 5),
+                    opener: opener("|", pos(3, 1, 4)),
+                })
+            );
+        }
+
+        #[test]
+        fn test_min_stack() {
+            let mut stack = MinStack::new();
+            assert_eq!(stack.min(), None);
+            for x in [5, 3, 7, 3, 1, 8] {
+                stack.push(x);
             }
+            assert_eq!(stack.len(), 6);
+            assert_eq!(stack.peek(), Some(&8));
+            assert_eq!(stack.min(), Some(&1));
 
-            impl Drop for DropCounter {
-                fn drop(&mut self) {
-                    self.count.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(stack.pop(), Some(8));
+            assert_eq!(stack.pop(), Some(1));
+            // 最小値 3 が 2 つあるので、1 つ pop しても 3 のまま
+            assert_eq!(stack.min(), Some(&3));
+            stack.pop();
+            assert_eq!(stack.min(), Some(&3));
+            stack.pop();
+            assert_eq!(stack.min(), Some(&3));
+            stack.pop();
+            assert_eq!(stack.min(), Some(&5));
+            stack.pop();
+            assert_eq!(stack.min(), None);
+            assert!(stack.is_empty());
+        }
+
+        #[test]
+        fn test_max_stack() {
+            let mut stack = MaxStack::new();
+            for s in ["b", "d", "a", "d"] {
+                stack.push(s);
+            }
+            assert_eq!(stack.max(), Some(&"d"));
+            assert_eq!(stack.pop(), Some("d"));
+            assert_eq!(stack.max(), Some(&"d"));
+            assert_eq!(stack.pop(), Some("a"));
+            assert_eq!(stack.pop(), Some("d"));
+            assert_eq!(stack.max(), Some(&"b"));
+            assert_eq!(stack.peek(), Some(&"b"));
+        }
+
+        #[test]
+        fn test_min_max_stack_against_brute_force() {
+            let mut rng = SplitMix64::new(99);
+            let mut min_stack = MinStack::new();
+            let mut max_stack = MaxStack::new();
+            let mut reference: Vec<u32> = Vec::new();
+
+            for _ in 0..10_000 {
+                if rng.next_u64().is_multiple_of(3) {
+                    assert_eq!(min_stack.pop(), reference.last().copied());
+                    assert_eq!(max_stack.pop(), reference.pop());
+                } else {
+                    let x = (rng.next_u64() % 50) as u32;
+                    min_stack.push(x);
+                    max_stack.push(x);
+                    reference.push(x);
                 }
+                assert_eq!(min_stack.min(), reference.iter().min());
+                assert_eq!(max_stack.max(), reference.iter().max());
+                assert_eq!(min_stack.len(), reference.len());
             }
+        }
 
-            let drop_count = Arc::new(AtomicUsize::new(0));
+        #[test]
+        fn test_monotonic_stack_pop_if() {
+            let mut stack = MonotonicStack::increasing();
+            stack.push(0, 1);
+            stack.push(1, 4);
+            assert_eq!(stack.pop_if(&5), None);
+            // 4 以上の要素を取り除く（狭義増加なので 4 も取り除く）
+            assert_eq!(stack.pop_if(&4), Some((1, 4)));
+            assert_eq!(stack.pop_if(&4), None);
+            stack.push(2, 4);
+            assert_eq!(stack.len(), 2);
+            assert_eq!(stack.peek(), Some(&(2, 4)));
+        }
 
-            {
-                let mut vec = MyVec::new();
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
-                vec.push(DropCounter {
-                    count: drop_count.clone(),
-                });
+        #[test]
+        #[should_panic(expected = "monotonic order")]
+        fn test_monotonic_stack_push_out_of_order() {
+            let mut stack = MonotonicStack::decreasing();
+            stack.push(0, 1);
+            stack.push(1, 1);
+        }
+
+        /// 小さい値の乱数列（同じ値が多く出るようにする）
+        fn random_values(rng: &mut SplitMix64, len: usize) -> Vec<u64> {
+            (0..len).map(|_| rng.next_u64() % 10).collect()
+        }
+
+        #[test]
+        fn test_next_greater_element() {
+            assert_eq!(
+                next_greater_element(&[4, 5, 2, 25]),
+                vec![Some(1), Some(3), Some(3), None]
+            );
+
+            let brute_force = |values: &[u64]| -> Vec<Option<usize>> {
+                (0..values.len())
+                    .map(|i| (i + 1..values.len()).find(|&j| values[j] > values[i]))
+                    .collect()
+            };
+            let mut rng = SplitMix64::new(1);
+            for len in 0..200 {
+                let values = random_values(&mut rng, len);
+                assert_eq!(next_greater_element(&values), brute_force(&values));
+            }
+        }
+
+        #[test]
+        fn test_stock_span() {
+            assert_eq!(
+                stock_span(&[100, 80, 60, 70, 60, 75, 85]),
+                vec![1, 1, 1, 2, 1, 4, 6]
+            );
+
+            let brute_force = |prices: &[u64]| -> Vec<usize> {
+                (0..prices.len())
+                    .map(|i| {
+                        (0..=i)
+                            .rev()
+                            .take_while(|&j| prices[j] <= prices[i])
+                            .count()
+                    })
+                    .collect()
+            };
+            let mut rng = SplitMix64::new(2);
+            for len in 0..200 {
+                let prices = random_values(&mut rng, len);
+                assert_eq!(stock_span(&prices), brute_force(&prices));
+            }
+        }
+
+        #[test]
+        fn test_largest_rectangle() {
+            assert_eq!(largest_rectangle(&[2, 1, 5, 6, 2, 3]), 10);
+            assert_eq!(largest_rectangle(&[]), 0);
+            assert_eq!(largest_rectangle(&[3, 3, 3]), 9);
+            assert_eq!(
+                largest_rectangle(&[u64::MAX, u64::MAX]),
+                u64::MAX as u128 * 2
+            );
+
+            // すべての区間 [i, j] について、最も低い棒の高さ × 幅を調べる
+            let brute_force = |heights: &[u64]| -> u128 {
+                let mut best = 0;
+                for i in 0..heights.len() {
+                    let mut lowest = u64::MAX;
+                    for (width, &height) in heights[i..].iter().enumerate() {
+                        lowest = lowest.min(height);
+                        best = best.max(lowest as u128 * (width + 1) as u128);
+                    }
+                }
+                best
+            };
+            let mut rng = SplitMix64::new(3);
+            for len in 0..200 {
+                let heights = random_values(&mut rng, len);
+                assert_eq!(largest_rectangle(&heights), brute_force(&heights));
+            }
+        }
+
+        /// どの格納先でも同じ結果になる一連の操作
+        fn exercise_storage<S: StackStorage<i32>>(mut stack: Stack<i32, S>) {
+            assert!(stack.is_empty());
+            assert_eq!(stack.peek(), None);
+            for x in 1..=3 {
+                stack.push(x);
+            }
+            assert_eq!(stack.len(), 3);
+            assert_eq!(stack.peek(), Some(&3));
+            *stack.peek_mut().unwrap() = 30;
+            assert_eq!(stack.pop(), Some(30));
+            assert_eq!(stack.pop(), Some(2));
+            stack.push(4);
+            assert_eq!(stack.pop(), Some(4));
+            assert_eq!(stack.pop(), Some(1));
+            assert_eq!(stack.pop(), None);
+            assert!(stack.is_empty());
+        }
+
+        #[test]
+        fn test_stack_storages() {
+            exercise_storage(Stack::new());
+            exercise_storage(Stack::with_storage(MyVec::new()));
+            exercise_storage(Stack::with_storage(ArrayVec::<i32, 4>::new()));
+            exercise_storage(Stack::with_storage(LinkedList::new()));
+            exercise_storage(Stack::<i32, LinkedList<i32>>::default());
+        }
+
+        #[test]
+        fn test_stack_with_existing_storage() {
+            let stack = Stack::with_storage(vec![1, 2]);
+            assert_eq!(stack.peek(), Some(&2));
+
+            // 連結リストは先頭がトップ
+            let list: LinkedList<i32> = [1, 2].into_iter().collect();
+            let mut stack = Stack::with_storage(list);
+            assert_eq!(stack.pop(), Some(1));
+            stack.push(5);
+            let list = stack.into_storage();
+            assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![5, 2]);
+        }
+
+        #[test]
+        fn test_stack_array_vec_full() {
+            let mut stack: Stack<i32, ArrayVec<i32, 2>> = Stack::default();
+            assert_eq!(stack.try_push(1), Ok(()));
+            assert_eq!(stack.try_push(2), Ok(()));
+            assert_eq!(stack.try_push(3), Err(3));
+            assert_eq!(stack.len(), 2);
+            assert_eq!(stack.pop(), Some(2));
+            assert_eq!(stack.try_push(3), Ok(()));
+        }
 
-                // 1つpopする（この時点で1回dropされる）
-                let _popped = vec.pop();
-                drop(_popped); // 明示的にdrop
+        #[test]
+        #[should_panic(expected = "storage is full")]
+        fn test_stack_push_full_panics() {
+            let mut stack: Stack<i32, ArrayVec<i32, 1>> = Stack::default();
+            stack.push(1);
+            stack.push(2);
+        }
 
-                assert_eq!(drop_count.load(Ordering::SeqCst), 1);
+        #[test]
+        fn test_bounded_stack_reject() {
+            let mut stack = BoundedStack::new(2, OverflowPolicy::Reject);
+            assert_eq!(stack.push(1), Ok(None));
+            assert_eq!(stack.push(2), Ok(None));
+            assert!(stack.is_full());
+            assert_eq!(stack.push(3), Err(3));
+            assert_eq!(stack.len(), 2);
+            assert_eq!(stack.peek(), Some(&2));
+            assert_eq!(stack.pop(), Some(2));
+            assert_eq!(stack.push(3), Ok(None));
+            assert_eq!(stack.capacity(), 2);
+            assert_eq!(stack.policy(), OverflowPolicy::Reject);
+        }
 
-                // 残り2つはMyVecのdropで処理される
+        #[test]
+        fn test_bounded_stack_drop_oldest() {
+            let mut stack = BoundedStack::new(3, OverflowPolicy::DropOldest);
+            for x in 1..=3 {
+                assert_eq!(stack.push(x), Ok(None));
             }
+            assert_eq!(stack.push(4), Ok(Some(1)));
+            assert_eq!(stack.push(5), Ok(Some(2)));
+            *stack.peek_mut().unwrap() = 50;
 
-            // 合計3回dropが呼ばれたか確認
-            assert_eq!(drop_count.load(Ordering::SeqCst), 3);
+            assert_eq!(stack.pop(), Some(50));
+            assert_eq!(stack.pop(), Some(4));
+            assert_eq!(stack.pop(), Some(3));
+            assert_eq!(stack.pop(), None);
+
+            stack.push(1).unwrap();
+            stack.clear();
+            assert!(stack.is_empty());
         }
 
         #[test]
-        fn test_drop_empty_vec() {
-            // 空のベクタのdropでパニックしないか確認
-            let vec: MyVec<i32> = MyVec::new();
-            drop(vec); // 明示的にdrop
+        fn test_bounded_stack_drop_oldest_against_brute_force() {
+            // 上限付きの履歴は「すべての push を覚えた Vec の末尾 capacity 件」と一致する
+            let mut rng = SplitMix64::new(7);
+            let capacity = 5;
+            let mut stack = BoundedStack::new(capacity, OverflowPolicy::DropOldest);
+            let mut reference: Vec<u64> = Vec::new();
+
+            for _ in 0..5_000 {
+                if rng.next_u64().is_multiple_of(3) {
+                    assert_eq!(stack.pop(), reference.pop());
+                } else {
+                    let x = rng.next_u64() % 100;
+                    let dropped = stack.push(x).unwrap();
+                    reference.push(x);
+                    if reference.len() > capacity {
+                        assert_eq!(dropped, Some(reference.remove(0)));
+                    } else {
+                        assert_eq!(dropped, None);
+                    }
+                }
+                assert_eq!(stack.len(), reference.len());
+                assert_eq!(stack.peek(), reference.last());
+            }
         }
 
         #[test]
-        fn test_drop_with_capacity_but_no_elements() {
-            // 容量はあるが要素がない場合
-            let vec: MyVec<String> = MyVec::with_capacity(10);
-            drop(vec); // パニックしないはず
+        #[should_panic(expected = "capacity 1 exceeded")]
+        fn test_bounded_stack_panic_policy() {
+            let mut stack = BoundedStack::new(1, OverflowPolicy::Panic);
+            stack.push(1).unwrap();
+            let _ = stack.push(2);
+        }
+
+        #[test]
+        #[should_panic(expected = "must be non-zero")]
+        fn test_bounded_stack_zero_capacity() {
+            let _ = BoundedStack::<i32>::new(0, OverflowPolicy::Reject);
         }
     }
 }
 
 // ---------------------------------------------------------
-// 課題2: 単方向連結リスト
+// 課題3-2: 数式の評価（スタックの応用）
 // ---------------------------------------------------------
 
-pub mod linked_list {
-    use std::fmt::Display;
+pub mod expr {
+    use super::stack::Stack;
+    use std::fmt;
 
-    /// ノード構造
-    struct Node<T> {
-        data: T,
-        next: Option<Box<Node<T>>>,
+    /// 字句の種類
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum TokenKind {
+        /// 数値（整数も浮動小数点数として扱う）
+        Number(f64),
+        /// 演算子 `+ - * / % ^`
+        Operator(char),
+        LeftParen,
+        RightParen,
+        Comma,
+        /// 関数名や定数名
+        Identifier(String),
     }
 
-    /// 単方向連結リスト
-    pub struct LinkedList<T> {
-        head: Option<Box<Node<T>>>,
-        len: usize,
+    /// 字句とその位置（入力の先頭からのバイトオフセット）
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Token {
+        pub kind: TokenKind,
+        pub position: usize,
     }
 
-    impl<T> LinkedList<T> {
-        /// 新しい空のリストを作成
-        pub fn new() -> Self {
-            LinkedList { head: None, len: 0 }
-        }
-
-        /// 先頭に要素を追加
-        pub fn push_front(&mut self, data: T) {
-            // 1. 新しいノードを作成
-            // 2. 新しいノードの next に現在の head を設定
-            let new_node = Node {
-                data,
-                next: self.head.take(),
-            };
-            // 3. head を新しいノードに更新
-            self.head = Some(Box::new(new_node));
-            // 4. len をインクリメント
-            self.len += 1;
-        }
+    /// 組み込み関数
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Function {
+        Min,
+        Max,
+        Sqrt,
+        Abs,
+    }
 
-        /// 先頭の要素を削除して返す
-        pub fn pop_front(&mut self) -> Option<T> {
-            // match で先頭ノードを評価
-            match self.head.take() {
-                Some(node) => {
-                    // 新たな head に取り出した Node.next をセット
-                    self.head = node.next;
-                    self.len -= 1;
-                    Some(node.data)
-                }
-                None => None,
+    impl Function {
+        fn from_name(name: &str) -> Option<Self> {
+            match name {
+                "min" => Some(Function::Min),
+                "max" => Some(Function::Max),
+                "sqrt" => Some(Function::Sqrt),
+                "abs" => Some(Function::Abs),
+                _ => None,
             }
         }
 
-        /// 末尾に要素を追加
-        pub fn push_back(&mut self, data: T) {
-            // TODO: 実装してください（チャレンジ課題）
-            // ヒント: 末尾まで辿る必要がある
-            let new_node = Box::new(Node { data, next: None });
-
-            match self.head {
-                None => {
-                    self.head = Some(new_node);
-                }
-                Some(ref mut head) => {
-                    let mut current = head;
-                    while current.next.is_some() {
-                        current = current.next.as_mut().unwrap();
-                    }
-
-                    current.next = Some(new_node);
-                }
+        fn name(self) -> &'static str {
+            match self {
+                Function::Min => "min",
+                Function::Max => "max",
+                Function::Sqrt => "sqrt",
+                Function::Abs => "abs",
             }
-            self.len += 1;
         }
 
-        /// 先頭の要素を参照
-        pub fn peek(&self) -> Option<&T> {
-            self.head.as_ref().map(|node| &node.data)
+        /// 受け付ける引数の個数（最小, 最大）
+        fn arity(self) -> (usize, Option<usize>) {
+            match self {
+                Function::Min | Function::Max => (2, None),
+                Function::Sqrt | Function::Abs => (1, Some(1)),
+            }
         }
+    }
 
-        /// 要素数
-        pub fn len(&self) -> usize {
-            self.len
-        }
+    /// 逆ポーランド記法（RPN）の要素
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum RpnKind {
+        Number(f64),
+        /// 二項演算子
+        Binary(char),
+        /// 単項マイナス
+        Negate,
+        /// 関数呼び出し（引数の個数つき）
+        Call(Function, usize),
+    }
 
-        /// 空かどうか
-        pub fn is_empty(&self) -> bool {
-            self.head.is_none()
-        }
+    /// RPN の要素と、元の式での位置
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RpnToken {
+        pub kind: RpnKind,
+        pub position: usize,
+    }
 
-        pub fn iter(&self) -> Iter<T> {
-            Iter {
-                current: self.head.as_ref().map(|node| &**node),
+    impl fmt::Display for RpnToken {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.kind {
+                RpnKind::Number(n) => write!(f, "{}", n),
+                RpnKind::Binary(op) => write!(f, "{}", op),
+                RpnKind::Negate => write!(f, "neg"),
+                RpnKind::Call(function, argc) => write!(f, "{}/{}", function.name(), argc),
             }
         }
     }
 
-    pub struct Iter<'a, T> {
-        current: Option<&'a Node<T>>,
+    /// エラーの種類
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ExprErrorKind {
+        /// 数式に使えない文字
+        UnexpectedChar(char),
+        /// 数値として読めない
+        InvalidNumber(String),
+        /// 未定義の関数名・定数名
+        UnknownIdentifier(String),
+        /// その位置に来てはいけない字句
+        UnexpectedToken,
+        /// 式が途中で終わっている
+        UnexpectedEnd,
+        /// 閉じられていない `(`
+        UnclosedParen,
+        /// 対応する `(` のない `)`
+        UnmatchedCloseParen,
+        /// 関数の引数の個数が違う
+        WrongArity {
+            function: &'static str,
+            found: usize,
+        },
+        /// 0 による除算（`/` と `%`）
+        DivisionByZero,
+        /// 関数の定義域の外（負の数の sqrt など）
+        DomainError,
+        /// RPN として評価できない（被演算子の過不足、未知の演算子など）
+        MalformedRpn,
     }
 
-    impl<'a, T> Iterator for Iter<'a, T> {
-        type Item = &'a T;
+    /// 数式のエラー（position は入力の先頭からのバイトオフセット）
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ExprError {
+        pub kind: ExprErrorKind,
+        pub position: usize,
+    }
 
-        fn next(&mut self) -> Option<Self::Item> {
-            self.current.map(|node| {
-                self.current = node.next.as_ref().map(|n| &**n);
-                &node.data
-            })
+    impl ExprError {
+        fn new(kind: ExprErrorKind, position: usize) -> Self {
+            ExprError { kind, position }
         }
     }
 
-    impl<T: Display> LinkedList<T> {
-        /// リストを表示（デバッグ用）
-        pub fn display(&self) {
-            let mut current = &self.head;
-            print!("LinkedList: ");
-            while let Some(node) = current {
-                print!("{} -> ", node.data);
-                current = &node.next;
+    impl fmt::Display for ExprError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.kind {
+                ExprErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c)?,
+                ExprErrorKind::InvalidNumber(s) => write!(f, "invalid number '{}'", s)?,
+                ExprErrorKind::UnknownIdentifier(name) => {
+                    write!(f, "unknown identifier '{}'", name)?
+                }
+                ExprErrorKind::UnexpectedToken => write!(f, "unexpected token")?,
+                ExprErrorKind::UnexpectedEnd => write!(f, "unexpected end of expression")?,
+                ExprErrorKind::UnclosedParen => write!(f, "unclosed '('")?,
+                ExprErrorKind::UnmatchedCloseParen => write!(f, "unmatched ')'")?,
+                ExprErrorKind::WrongArity { function, found } => write!(
+                    f,
+                    "wrong number of arguments to '{}': found {}",
+                    function, found
+                )?,
+                ExprErrorKind::DivisionByZero => write!(f, "division by zero")?,
+                ExprErrorKind::DomainError => write!(f, "argument out of domain")?,
+                ExprErrorKind::MalformedRpn => write!(f, "malformed RPN")?,
             }
-            println!("None");
+            write!(f, " at position {}", self.position)
         }
     }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-
-        #[test]
-        fn test_new() {
-            let list: LinkedList<i32> = LinkedList::new();
-            assert_eq!(list.len(), 0);
-            assert!(list.is_empty());
-        }
+    impl std::error::Error for ExprError {}
 
-        #[test]
-        fn test_push_front() {
-            let mut list = LinkedList::new();
-            list.push_front(1);
-            list.push_front(2);
-            list.push_front(3);
+    /// 数式を字句に分割する
+    ///
+    /// # Examples
+    /// ```
+    /// let tokens = tokenize("1 + x").unwrap();
+    /// assert_eq!(tokens[2].kind, TokenKind::Identifier("x".to_string()));
+    /// assert_eq!(tokens[2].position, 4);
+    /// ```
+    pub fn tokenize(input: &str) -> Result<Vec<Token>, ExprError> {
+        let mut tokens = Vec::new();
+        let mut chars = input.char_indices().peekable();
 
-            assert_eq!(list.len(), 3);
-            assert_eq!(list.peek(), Some(&3));
+        while let Some(&(position, c)) = chars.peek() {
+            let kind = match c {
+                c if c.is_whitespace() => {
+                    chars.next();
+                    continue;
+                }
+                '0'..='9' | '.' => {
+                    let end = number_end(input, position);
+                    let text = &input[position..end];
+                    let value = text.parse::<f64>().map_err(|_| {
+                        ExprError::new(ExprErrorKind::InvalidNumber(text.to_string()), position)
+                    })?;
+                    while chars.peek().is_some_and(|&(i, _)| i < end) {
+                        chars.next();
+                    }
+                    tokens.push(Token {
+                        kind: TokenKind::Number(value),
+                        position,
+                    });
+                    continue;
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    let mut end = position;
+                    while let Some(&(i, c)) = chars.peek() {
+                        if !(c.is_alphanumeric() || c == '_') {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    tokens.push(Token {
+                        kind: TokenKind::Identifier(input[position..end].to_string()),
+                        position,
+                    });
+                    continue;
+                }
+                '+' | '-' | '*' | '/' | '%' | '^' => TokenKind::Operator(c),
+                '(' => TokenKind::LeftParen,
+                ')' => TokenKind::RightParen,
+                ',' => TokenKind::Comma,
+                _ => return Err(ExprError::new(ExprErrorKind::UnexpectedChar(c), position)),
+            };
+            chars.next();
+            tokens.push(Token { kind, position });
         }
 
-        #[test]
-        fn test_pop_front() {
-            let mut list = LinkedList::new();
-            list.push_front(1);
-            list.push_front(2);
+        Ok(tokens)
+    }
 
-            assert_eq!(list.pop_front(), Some(2));
-            assert_eq!(list.pop_front(), Some(1));
-            assert_eq!(list.pop_front(), None);
+    /// start から始まる数値リテラルの終わり（`12`, `1.5`, `.5`, `2.`, `1e-3` など）
+    ///
+    /// 数字と `.` はまとめて読むので、`1.2.3` は 1 つの不正な数値になる。
+    fn number_end(input: &str, start: usize) -> usize {
+        let bytes = input.as_bytes();
+        let digits = |mut i: usize, allow_dot: bool| {
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || (allow_dot && bytes[i] == b'.'))
+            {
+                i += 1;
+            }
+            i
+        };
+
+        let mut end = digits(start, true);
+        // 指数部は e の後に数字が続くときだけ（続かなければ e は識別子）
+        if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+            let mut i = end + 1;
+            if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+                i += 1;
+            }
+            if i < bytes.len() && bytes[i].is_ascii_digit() {
+                end = digits(i, false);
+            }
         }
+        end
     }
-}
-
-// ---------------------------------------------------------
-// 課題3: スタック（LIFO）
-// ---------------------------------------------------------
 
-pub mod stack {
-    /// スタックの実装
-    /// Vec を内部で使用する簡易版
-    pub struct Stack<T> {
-        items: Vec<T>,
+    /// 演算子スタックに積むもの
+    enum Pending {
+        Binary(char, usize),
+        Negate(usize),
+        /// `(`。関数呼び出しの括弧なら関数と、これまでに区切った引数の数を持つ
+        Paren {
+            position: usize,
+            call: Option<(Function, usize, usize)>, // (関数, 関数名の位置, カンマの数)
+        },
     }
 
-    impl<T> Stack<T> {
-        /// 新しい空のスタックを作成
-        pub fn new() -> Self {
-            Stack { items: Vec::new() }
+    /// 演算子の優先順位と、右結合かどうか
+    fn precedence(op: char) -> (u8, bool) {
+        match op {
+            '+' | '-' => (1, false),
+            '*' | '/' | '%' => (2, false),
+            '^' => (4, true),
+            _ => unreachable!("unknown operator {}", op),
         }
+    }
 
-        /// 要素をプッシュ
-        pub fn push(&mut self, item: T) {
-            self.items.push(item);
-        }
+    /// 単項マイナスの優先順位（`*` より強く `^` より弱い: -2^2 = -(2^2)）
+    const NEGATE_PRECEDENCE: u8 = 3;
 
-        /// 要素をポップ
-        pub fn pop(&mut self) -> Option<T> {
-            self.items.pop()
+    /// 操車場アルゴリズムで中置記法の式を RPN に変換する
+    ///
+    /// # アルゴリズム
+    /// 数値はそのまま出力し、演算子は演算子スタックに積む。新しい演算子を積む前に、
+    /// それより優先順位が高い（左結合なら同じものも）演算子をスタックから出力へ移す。
+    /// `)` が来たら対応する `(` までを出力へ移す。
+    ///
+    /// # Examples
+    /// ```
+    /// let rpn = to_rpn("3 + 4 * 2").unwrap();
+    /// let text: Vec<String> = rpn.iter().map(|t| t.to_string()).collect();
+    /// assert_eq!(text.join(" "), "3 4 2 * +");
+    /// ```
+    pub fn to_rpn(input: &str) -> Result<Vec<RpnToken>, ExprError> {
+        let tokens = tokenize(input)?;
+        let mut output = Vec::new();
+        let mut operators: Stack<Pending> = Stack::new();
+        // true なら次はオペランド（数値、`(`、関数、単項マイナス）が来るべき位置
+        let mut expect_operand = true;
+        let mut tokens = tokens.into_iter().peekable();
+
+        while let Some(Token { kind, position }) = tokens.next() {
+            let unexpected = ExprError::new(ExprErrorKind::UnexpectedToken, position);
+            match kind {
+                TokenKind::Number(value) => {
+                    if !expect_operand {
+                        return Err(unexpected);
+                    }
+                    output.push(RpnToken {
+                        kind: RpnKind::Number(value),
+                        position,
+                    });
+                    expect_operand = false;
+                }
+                TokenKind::Identifier(name) => {
+                    if !expect_operand {
+                        return Err(unexpected);
+                    }
+                    let unknown =
+                        ExprError::new(ExprErrorKind::UnknownIdentifier(name.clone()), position);
+                    let is_call = tokens
+                        .peek()
+                        .is_some_and(|t| t.kind == TokenKind::LeftParen);
+                    if is_call {
+                        let function = Function::from_name(&name).ok_or(unknown)?;
+                        let paren = tokens.next().expect("peeked");
+                        operators.push(Pending::Paren {
+                            position: paren.position,
+                            call: Some((function, position, 0)),
+                        });
+                    } else {
+                        let value = match name.as_str() {
+                            "pi" => std::f64::consts::PI,
+                            "e" => std::f64::consts::E,
+                            _ => return Err(unknown),
+                        };
+                        output.push(RpnToken {
+                            kind: RpnKind::Number(value),
+                            position,
+                        });
+                        expect_operand = false;
+                    }
+                }
+                TokenKind::Operator('-') if expect_operand => {
+                    // 前置演算子は何も取り出さずに積む
+                    operators.push(Pending::Negate(position));
+                }
+                TokenKind::Operator(op) => {
+                    if expect_operand {
+                        return Err(unexpected);
+                    }
+                    let (prec, right_assoc) = precedence(op);
+                    while let Some(top) = operators.peek() {
+                        let top_prec = match top {
+                            Pending::Binary(top_op, _) => precedence(*top_op).0,
+                            Pending::Negate(_) => NEGATE_PRECEDENCE,
+                            Pending::Paren { .. } => break,
+                        };
+                        if top_prec > prec || (top_prec == prec && !right_assoc) {
+                            let top = operators.pop().expect("peeked");
+                            output.push(emit(top));
+                        } else {
+                            break;
+                        }
+                    }
+                    operators.push(Pending::Binary(op, position));
+                    expect_operand = true;
+                }
+                TokenKind::LeftParen => {
+                    if !expect_operand {
+                        return Err(unexpected);
+                    }
+                    operators.push(Pending::Paren {
+                        position,
+                        call: None,
+                    });
+                }
+                TokenKind::Comma => {
+                    if expect_operand {
+                        return Err(unexpected);
+                    }
+                    pop_until_paren(&mut operators, &mut output);
+                    // カンマは関数呼び出しの括弧の中でだけ使える
+                    match operators.peek_mut() {
+                        Some(Pending::Paren {
+                            call: Some((_, _, commas)),
+                            ..
+                        }) => *commas += 1,
+                        _ => return Err(unexpected),
+                    }
+                    expect_operand = true;
+                }
+                TokenKind::RightParen => {
+                    if expect_operand {
+                        return Err(unexpected);
+                    }
+                    pop_until_paren(&mut operators, &mut output);
+                    match operators.pop() {
+                        Some(Pending::Paren { call, .. }) => {
+                            if let Some((function, name_position, commas)) = call {
+                                let argc = commas + 1;
+                                let (min, max) = function.arity();
+                                if argc < min || max.is_some_and(|max| argc > max) {
+                                    return Err(ExprError::new(
+                                        ExprErrorKind::WrongArity {
+                                            function: function.name(),
+                                            found: argc,
+                                        },
+                                        name_position,
+                                    ));
+                                }
+                                output.push(RpnToken {
+                                    kind: RpnKind::Call(function, argc),
+                                    position: name_position,
+                                });
+                            }
+                        }
+                        _ => {
+                            return Err(ExprError::new(
+                                ExprErrorKind::UnmatchedCloseParen,
+                                position,
+                            ))
+                        }
+                    }
+                }
+            }
         }
 
-        /// トップの要素を参照
-        pub fn peek(&self) -> Option<&T> {
-            self.items.last()
+        if expect_operand {
+            return Err(ExprError::new(ExprErrorKind::UnexpectedEnd, input.len()));
+        }
+        while let Some(pending) = operators.pop() {
+            if let Pending::Paren { position, .. } = pending {
+                return Err(ExprError::new(ExprErrorKind::UnclosedParen, position));
+            }
+            output.push(emit(pending));
         }
+        Ok(output)
+    }
 
-        /// 空かどうか
-        pub fn is_empty(&self) -> bool {
-            self.len() == 0
+    /// 直近の `(` の手前まで、演算子を出力へ移す（`(` は残す）
+    fn pop_until_paren(operators: &mut Stack<Pending>, output: &mut Vec<RpnToken>) {
+        while let Some(top) = operators.peek() {
+            if let Pending::Paren { .. } = top {
+                break;
+            }
+            let top = operators.pop().expect("peeked");
+            output.push(emit(top));
         }
+    }
 
-        /// 要素数
-        pub fn len(&self) -> usize {
-            self.items.len()
+    /// 演算子スタックから取り出した演算子を RPN の要素にする
+    fn emit(pending: Pending) -> RpnToken {
+        match pending {
+            Pending::Binary(op, position) => RpnToken {
+                kind: RpnKind::Binary(op),
+                position,
+            },
+            Pending::Negate(position) => RpnToken {
+                kind: RpnKind::Negate,
+                position,
+            },
+            Pending::Paren { .. } => unreachable!("parentheses are never emitted"),
         }
     }
 
-    /// 括弧のバランスをチェックする関数（応用問題）
+    /// RPN をスタックで評価する
     ///
-    /// # Examples
-    /// ```
-    /// assert_eq!(check_balanced_parentheses("()"), true);
-    /// assert_eq!(check_balanced_parentheses("(()"), false);
-    /// assert_eq!(check_balanced_parentheses("{[()]}"), true);
-    /// ```
-    pub fn check_balanced_parentheses(s: &str) -> bool {
-        // TODO: 実装してください
-        // ヒント:
-        // 1. 開き括弧が来たらスタックにプッシュ
-        // 2. 閉じ括弧が来たらスタックからポップして対応を確認
-        // 3. 最後にスタックが空なら true
-        let mut stack = Stack::new();
-        for c in s.chars() {
-            match c {
-                '(' | '[' | '{' => {
-                    stack.push(c);
-                }
-                ')' => {
-                    if stack.pop() != Some('(') {
-                        return false;
+    /// 数値はスタックに積み、演算子は必要な数だけ取り出して計算した結果を積む。
+    /// 手で組み立てた RPN も受け付けるので、被演算子が足りない・余る、
+    /// 未知の演算子、引数の個数が合わない関数呼び出しは `MalformedRpn` になる。
+    pub fn eval_rpn(rpn: &[RpnToken]) -> Result<f64, ExprError> {
+        let mut stack: Stack<f64> = Stack::new();
+
+        for token in rpn {
+            let error = |kind| ExprError::new(kind, token.position);
+            let mut pop = || {
+                stack
+                    .pop()
+                    .ok_or_else(|| error(ExprErrorKind::MalformedRpn))
+            };
+            let value = match &token.kind {
+                RpnKind::Number(value) => *value,
+                RpnKind::Negate => -pop()?,
+                RpnKind::Binary(op) => {
+                    let b = pop()?;
+                    let a = pop()?;
+                    match op {
+                        '+' => a + b,
+                        '-' => a - b,
+                        '*' => a * b,
+                        '/' | '%' if b == 0.0 => return Err(error(ExprErrorKind::DivisionByZero)),
+                        '/' => a / b,
+                        '%' => a % b,
+                        '^' => a.powf(b),
+                        _ => return Err(error(ExprErrorKind::MalformedRpn)),
                     }
                 }
-                ']' => {
-                    if stack.pop() != Some('[') {
-                        return false;
+                RpnKind::Call(function, argc) => {
+                    let (min, max) = function.arity();
+                    if *argc < min || max.is_some_and(|max| *argc > max) {
+                        return Err(error(ExprErrorKind::MalformedRpn));
                     }
-                }
-                '}' => {
-                    if stack.pop() != Some('{') {
-                        return false;
+                    let mut args = (0..*argc).map(|_| pop()).collect::<Result<Vec<f64>, _>>()?;
+                    args.reverse();
+                    match function {
+                        Function::Min => args.into_iter().fold(f64::INFINITY, f64::min),
+                        Function::Max => args.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                        Function::Sqrt if args[0] < 0.0 => {
+                            return Err(error(ExprErrorKind::DomainError))
+                        }
+                        Function::Sqrt => args[0].sqrt(),
+                        Function::Abs => args[0].abs(),
                     }
                 }
-                _ => {}
-            }
+            };
+            stack.push(value);
+        }
+
+        // 最後に値がちょうど 1 つ残っていなければならない
+        let position = rpn.last().map_or(0, |token| token.position);
+        match (stack.pop(), stack.is_empty()) {
+            (Some(result), true) => Ok(result),
+            _ => Err(ExprError::new(ExprErrorKind::MalformedRpn, position)),
         }
+    }
 
-        stack.is_empty()
+    /// 数式を評価する
+    ///
+    /// # Examples
+    /// ```
+    /// assert_eq!(eval("2 * (3 + 4)"), Ok(14.0));
+    /// assert_eq!(eval("max(1, -2^2, sqrt(9))"), Ok(3.0));
+    /// assert_eq!(eval("1 / (2 - 2)").unwrap_err().position, 2);
+    /// ```
+    pub fn eval(input: &str) -> Result<f64, ExprError> {
+        eval_rpn(&to_rpn(input)?)
     }
 
     #[cfg(test)]
     mod tests {
         use super::*;
 
+        fn rpn(input: &str) -> String {
+            let tokens = to_rpn(input).unwrap();
+            tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+
+        fn error(input: &str) -> (ExprErrorKind, usize) {
+            let err = eval(input).unwrap_err();
+            (err.kind, err.position)
+        }
+
         #[test]
-        fn test_stack_operations() {
-            let mut stack = Stack::new();
-            stack.push(1);
-            stack.push(2);
-            stack.push(3);
+        fn test_tokenize() {
+            let tokens = tokenize("12.5*(x_1 - .5e1)").unwrap();
+            let kinds: Vec<TokenKind> = tokens.iter().map(|t| t.kind.clone()).collect();
+            assert_eq!(
+                kinds,
+                vec![
+                    TokenKind::Number(12.5),
+                    TokenKind::Operator('*'),
+                    TokenKind::LeftParen,
+                    TokenKind::Identifier("x_1".to_string()),
+                    TokenKind::Operator('-'),
+                    TokenKind::Number(5.0),
+                    TokenKind::RightParen,
+                ]
+            );
+            let positions: Vec<usize> = tokens.iter().map(|t| t.position).collect();
+            assert_eq!(positions, vec![0, 4, 5, 6, 10, 12, 16]);
 
-            assert_eq!(stack.peek(), Some(&3));
-            assert_eq!(stack.pop(), Some(3));
-            assert_eq!(stack.pop(), Some(2));
-            assert_eq!(stack.len(), 1);
+            assert_eq!(
+                tokenize("1 $ 2").unwrap_err(),
+                ExprError::new(ExprErrorKind::UnexpectedChar('$'), 2)
+            );
+            assert_eq!(
+                tokenize("1.2.3").unwrap_err(),
+                ExprError::new(ExprErrorKind::InvalidNumber("1.2.3".to_string()), 0)
+            );
         }
 
         #[test]
-        fn test_balanced_parentheses() {
-            assert!(check_balanced_parentheses("()"));
-            assert!(check_balanced_parentheses("()[]{}"));
-            assert!(check_balanced_parentheses("{[()]}"));
-            assert!(!check_balanced_parentheses("(()"));
-            assert!(!check_balanced_parentheses("({)}"));
+        fn test_to_rpn() {
+            assert_eq!(
+                rpn("3 + 4 * 2 / (1 - 5) ^ 2 ^ 3"),
+                "3 4 2 * 1 5 - 2 3 ^ ^ / +"
+            );
+            assert_eq!(rpn("1 - 2 - 3"), "1 2 - 3 -");
+            assert_eq!(rpn("-2 ^ 2"), "2 2 ^ neg");
+            assert_eq!(rpn("-2 * 3"), "2 neg 3 *");
+            assert_eq!(rpn("2 ^ -1"), "2 1 neg ^");
+            assert_eq!(rpn("max(1, 2 + 3, min(4, 5))"), "1 2 3 + 4 5 min/2 max/3");
+        }
+
+        #[test]
+        fn test_eval() {
+            assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+            assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+            assert_eq!(eval("7 / 2"), Ok(3.5));
+            assert_eq!(eval("7 % 3"), Ok(1.0));
+            assert_eq!(eval("2 ^ 3 ^ 2"), Ok(512.0));
+            assert_eq!(eval("-2 ^ 2"), Ok(-4.0));
+            assert_eq!(eval("--3"), Ok(3.0));
+            assert_eq!(eval("2 * -(1 + 2)"), Ok(-6.0));
+            assert_eq!(eval("sqrt(16) + abs(-2)"), Ok(6.0));
+            assert_eq!(eval("min(3, 1, 2) + max(1, 5)"), Ok(6.0));
+            assert_eq!(eval("1.5e2 + .5"), Ok(150.5));
+            assert!((eval("2 * pi").unwrap() - std::f64::consts::TAU).abs() < 1e-12);
+        }
+
+        #[test]
+        fn test_mismatched_parens() {
+            assert_eq!(error("(1 + 2"), (ExprErrorKind::UnclosedParen, 0));
+            assert_eq!(error("((1) + (2)"), (ExprErrorKind::UnclosedParen, 0));
+            assert_eq!(error("1 + 2)"), (ExprErrorKind::UnmatchedCloseParen, 5));
+            assert_eq!(error("max(1, 2"), (ExprErrorKind::UnclosedParen, 3));
+            assert_eq!(error("()"), (ExprErrorKind::UnexpectedToken, 1));
+        }
+
+        #[test]
+        fn test_division_by_zero() {
+            assert_eq!(error("1 / 0"), (ExprErrorKind::DivisionByZero, 2));
+            assert_eq!(
+                error("10 - 5 % (2 - 2)"),
+                (ExprErrorKind::DivisionByZero, 7)
+            );
+            assert_eq!(
+                eval("1 / (1 - 1)").unwrap_err().to_string(),
+                "division by zero at position 2"
+            );
+        }
+
+        #[test]
+        fn test_unknown_identifier_and_arity() {
+            assert_eq!(
+                error("1 + foo"),
+                (ExprErrorKind::UnknownIdentifier("foo".to_string()), 4)
+            );
+            assert_eq!(
+                error("2 * cos(0)"),
+                (ExprErrorKind::UnknownIdentifier("cos".to_string()), 4)
+            );
+            assert_eq!(
+                error("1 + sqrt(1, 2)"),
+                (
+                    ExprErrorKind::WrongArity {
+                        function: "sqrt",
+                        found: 2
+                    },
+                    4
+                )
+            );
+            assert_eq!(
+                error("max(1)"),
+                (
+                    ExprErrorKind::WrongArity {
+                        function: "max",
+                        found: 1
+                    },
+                    0
+                )
+            );
+            assert_eq!(error("sqrt(-1)"), (ExprErrorKind::DomainError, 0));
+        }
+
+        #[test]
+        fn test_syntax_errors() {
+            assert_eq!(error(""), (ExprErrorKind::UnexpectedEnd, 0));
+            assert_eq!(error("1 +"), (ExprErrorKind::UnexpectedEnd, 3));
+            assert_eq!(error("1 2"), (ExprErrorKind::UnexpectedToken, 2));
+            assert_eq!(error("* 2"), (ExprErrorKind::UnexpectedToken, 0));
+            assert_eq!(error("2 (3)"), (ExprErrorKind::UnexpectedToken, 2));
+            assert_eq!(error("1, 2"), (ExprErrorKind::UnexpectedToken, 1));
+            assert_eq!(error("(1, 2)"), (ExprErrorKind::UnexpectedToken, 2));
+            assert_eq!(error("max(1,, 2)"), (ExprErrorKind::UnexpectedToken, 6));
+        }
+
+        #[test]
+        fn test_eval_malformed_rpn() {
+            fn token(kind: RpnKind, position: usize) -> RpnToken {
+                RpnToken { kind, position }
+            }
+            let malformed = |rpn: &[RpnToken]| {
+                let err = eval_rpn(rpn).unwrap_err();
+                (err.kind, err.position)
+            };
+            let one = || token(RpnKind::Number(1.0), 0);
+
+            assert_eq!(malformed(&[]), (ExprErrorKind::MalformedRpn, 0));
+            // 被演算子が足りない
+            assert_eq!(
+                malformed(&[token(RpnKind::Negate, 3)]),
+                (ExprErrorKind::MalformedRpn, 3)
+            );
+            assert_eq!(
+                malformed(&[one(), token(RpnKind::Binary('+'), 2)]),
+                (ExprErrorKind::MalformedRpn, 2)
+            );
+            assert_eq!(
+                malformed(&[one(), token(RpnKind::Call(Function::Max, 3), 5)]),
+                (ExprErrorKind::MalformedRpn, 5)
+            );
+            // 未知の演算子
+            assert_eq!(
+                malformed(&[one(), one(), token(RpnKind::Binary('x'), 4)]),
+                (ExprErrorKind::MalformedRpn, 4)
+            );
+            // 引数の個数が関数に合わない
+            assert_eq!(
+                malformed(&[token(RpnKind::Call(Function::Sqrt, 0), 1)]),
+                (ExprErrorKind::MalformedRpn, 1)
+            );
+            assert_eq!(
+                malformed(&[one(), one(), token(RpnKind::Call(Function::Abs, 2), 6)]),
+                (ExprErrorKind::MalformedRpn, 6)
+            );
+            // 被演算子が余る
+            assert_eq!(
+                malformed(&[one(), token(RpnKind::Number(2.0), 7)]),
+                (ExprErrorKind::MalformedRpn, 7)
+            );
         }
     }
 }
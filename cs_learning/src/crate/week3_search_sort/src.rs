@@ -717,4 +717,117 @@ mod benchmarks {
             println!();
         }
     }
+
+    #[test]
+    #[ignore]
+    fn benchmark_small_vec_vs_my_vec() {
+        use crate::array_vec::small_vec::SmallVec;
+        use crate::week1_basic_structures::my_vec::MyVec;
+        use std::hint::black_box;
+
+        // 小さいベクターを大量に作って捨てるワークロード
+        let iterations = 100_000;
+        let sizes = vec![1, 4, 8, 16];
+
+        println!(
+            "\n=== SmallVec<_, 8> vs MyVec (push {} times) ===\n",
+            iterations
+        );
+
+        for size in sizes {
+            println!("Elements per vector: {}", size);
+
+            let start = Instant::now();
+            for _ in 0..iterations {
+                let mut vec = MyVec::new();
+                for i in 0..size {
+                    vec.push(black_box(i));
+                }
+                black_box(&vec);
+            }
+            let duration = start.elapsed();
+            println!("  MyVec:           {:?}", duration);
+
+            let start = Instant::now();
+            for _ in 0..iterations {
+                let mut vec: SmallVec<usize, 8> = SmallVec::new();
+                for i in 0..size {
+                    vec.push(black_box(i));
+                }
+                black_box(&vec);
+            }
+            let duration = start.elapsed();
+            println!("  SmallVec<_, 8>:  {:?}", duration);
+
+            println!();
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn benchmark_arena_list_vs_linked_list() {
+        use crate::week1_basic_structures::linked_list::{ArenaList, LinkedList};
+        use std::hint::black_box;
+
+        // 構築と走査の時間を比べる。ArenaList はノードが 1 本の配列に並ぶので、
+        // 走査時のメモリアクセスが Box ごとに散らばる LinkedList より局所的になる
+        let sizes = vec![1_000, 100_000, 1_000_000];
+
+        println!("\n=== ArenaList vs LinkedList (push_back + iterate) ===\n");
+
+        for size in sizes {
+            println!("List size: {}", size);
+
+            let start = Instant::now();
+            let mut list = LinkedList::new();
+            for i in 0..size {
+                list.push_back(black_box(i as u64));
+            }
+            let build = start.elapsed();
+            let start = Instant::now();
+            black_box(list.iter().sum::<u64>());
+            println!(
+                "  LinkedList:           build {:?}, iterate {:?}",
+                build,
+                start.elapsed()
+            );
+
+            let start = Instant::now();
+            let mut arena = ArenaList::new();
+            let handles: Vec<_> = (0..size)
+                .map(|i| arena.push_back(black_box(i as u64)))
+                .collect();
+            let build = start.elapsed();
+            let start = Instant::now();
+            black_box(arena.iter().sum::<u64>());
+            println!(
+                "  ArenaList:            build {:?}, iterate {:?}",
+                build,
+                start.elapsed()
+            );
+
+            // 半分を削除して先頭に挿入し直すと、リストの順序とアリーナ上の配置がずれる
+            for &handle in handles.iter().step_by(2) {
+                let value = arena.remove(handle).unwrap();
+                arena.push_front(value);
+            }
+            let start = Instant::now();
+            black_box(arena.iter().sum::<u64>());
+            println!("  ArenaList (shuffled): iterate {:?}", start.elapsed());
+
+            // compact でリストの順に並べ直す
+            let start = Instant::now();
+            arena.compact();
+            let compact = start.elapsed();
+            let start = Instant::now();
+            black_box(arena.iter().sum::<u64>());
+            println!(
+                "  ArenaList (compacted): compact {:?}, iterate {:?}",
+                compact,
+                start.elapsed()
+            );
+
+            println!();
+        }
+    }
 }
@@ -3,12 +3,33 @@
 #[path = "crate/week1_basic_structures/src.rs"]
 mod week1_basic_structures;
 
+// 固定長ベクター（ArrayVec）のモジュールを宣言
+// モジュールファイルの配置: src/crate/array_vec/src.rs
+#[path = "crate/array_vec/src.rs"]
+mod array_vec;
+
 // Week 3-4のモジュールを宣言
 // モジュールファイルの配置: src/crate/week3_search_sort/src.rs
 #[path = "crate/week3_search_sort/src.rs"]
 mod week3_search_sort;
 
 fn main() {
+    // 引数が渡されたら数式として評価する（例: cargo run -- "2 * (3 + 4)"）
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let input = args.join(" ");
+        match week1_basic_structures::expr::eval(&input) {
+            Ok(value) => println!("{}", value),
+            Err(err) => {
+                eprintln!("{}", input);
+                eprintln!("{}^", " ".repeat(input[..err.position].chars().count()));
+                eprintln!("error: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     println!("hash: {}", simple_hash("test_string"));
     println!("=== CS Learning with Rust ===");
     println!("コンピュータサイエンス学習プロジェクトへようこそ！");